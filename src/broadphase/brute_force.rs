@@ -0,0 +1,86 @@
+use crate::math::{aabb::Aabb, ray::Ray, vec2::Vec2};
+
+use super::{Broadphase, ProxyId, AABB_MARGIN, AABB_MULTIPLIER};
+
+/// Tests every pair of proxies. Only worth it for a handful of bodies, or as a baseline to compare against.
+#[derive(Default)]
+pub struct BruteForce {
+    // `None` marks a destroyed proxy whose slot can be reused.
+    proxies: Vec<Option<(Aabb, usize)>>,
+    free: Vec<usize>,
+}
+
+impl BruteForce {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Broadphase for BruteForce {
+    fn create_proxy(&mut self, aabb: Aabb, user_data: usize) -> ProxyId {
+        let proxy = Some((aabb.fattened(AABB_MARGIN), user_data));
+
+        match self.free.pop() {
+            Some(index) => {
+                self.proxies[index] = proxy;
+                ProxyId(index)
+            }
+            None => {
+                self.proxies.push(proxy);
+                ProxyId(self.proxies.len() - 1)
+            }
+        }
+    }
+
+    fn destroy_proxy(&mut self, proxy: ProxyId) {
+        self.proxies[proxy.0] = None;
+        self.free.push(proxy.0);
+    }
+
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: Aabb, displacement: Vec2) -> bool {
+        let Some((fat_aabb, _)) = &mut self.proxies[proxy.0] else {
+            return false;
+        };
+        if fat_aabb.contains(&aabb) {
+            return false;
+        }
+
+        *fat_aabb = aabb
+            .fattened(AABB_MARGIN)
+            .swept(displacement * AABB_MULTIPLIER);
+        true
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(usize) -> bool) {
+        for (fat_aabb, user_data) in self.proxies.iter().flatten() {
+            if fat_aabb.overlaps(aabb) && !callback(*user_data) {
+                return;
+            }
+        }
+    }
+
+    fn raycast(&self, ray: &Ray, mut max_t: f32, callback: &mut dyn FnMut(usize) -> f32) {
+        for (fat_aabb, user_data) in self.proxies.iter().flatten() {
+            if fat_aabb.raycast(ray, max_t).is_some() {
+                max_t = callback(*user_data);
+                if max_t < 0. {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn find_pairs(&mut self, pairs: &mut Vec<(usize, usize)>) {
+        for (i, a) in self.proxies.iter().enumerate() {
+            let Some((aabb_a, a)) = a else {
+                continue;
+            };
+
+            for (aabb_b, b) in self.proxies[(i + 1)..].iter().flatten() {
+                if aabb_a.overlaps(aabb_b) {
+                    pairs.push((*a.min(b), *a.max(b)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::math::{aabb::Aabb, ray::Ray, vec2::Vec2};
+
+use super::{Broadphase, ProxyId, AABB_MARGIN, AABB_MULTIPLIER};
+
+const NULL_NODE: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+struct TreeNode {
+    aabb: Aabb,
+    // Doubles as the next free node while the node is unused.
+    parent: usize,
+    child1: usize,
+    child2: usize,
+    // Leaves are 0, free nodes are -1.
+    height: i32,
+    user_data: usize,
+}
+
+impl TreeNode {
+    fn is_leaf(&self) -> bool {
+        self.child1 == NULL_NODE
+    }
+}
+
+/// Bounding volume hierarchy of fattened AABBs, kept balanced by rotations like an AVL tree.
+///
+/// Leaves hold a `user_data` index, in `Simulation` the index of the body.
+pub struct DynamicTree {
+    nodes: Vec<TreeNode>,
+    root: usize,
+    free_list: usize,
+}
+
+impl Default for DynamicTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicTree {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            root: NULL_NODE,
+            free_list: NULL_NODE,
+        }
+    }
+
+    pub fn fat_aabb(&self, proxy: ProxyId) -> Aabb {
+        self.nodes[proxy.0].aabb
+    }
+
+    pub fn user_data(&self, proxy: ProxyId) -> usize {
+        self.nodes[proxy.0].user_data
+    }
+
+    pub fn height(&self) -> i32 {
+        if self.root == NULL_NODE {
+            0
+        } else {
+            self.nodes[self.root].height
+        }
+    }
+
+    fn allocate_node(&mut self) -> usize {
+        if self.free_list == NULL_NODE {
+            self.nodes.push(TreeNode {
+                aabb: Aabb::new(Default::default(), Default::default()),
+                parent: NULL_NODE,
+                child1: NULL_NODE,
+                child2: NULL_NODE,
+                height: 0,
+                user_data: 0,
+            });
+            return self.nodes.len() - 1;
+        }
+
+        let index = self.free_list;
+        self.free_list = self.nodes[index].parent;
+
+        let node = &mut self.nodes[index];
+        node.parent = NULL_NODE;
+        node.child1 = NULL_NODE;
+        node.child2 = NULL_NODE;
+        node.height = 0;
+        index
+    }
+
+    fn free_node(&mut self, index: usize) {
+        self.nodes[index].parent = self.free_list;
+        self.nodes[index].height = -1;
+        self.free_list = index;
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL_NODE {
+            self.root = leaf;
+            self.nodes[leaf].parent = NULL_NODE;
+            return;
+        }
+
+        // Walk down to the sibling that grows the least by adding the leaf.
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut index = self.root;
+        while !self.nodes[index].is_leaf() {
+            let node = &self.nodes[index];
+            let (child1, child2) = (node.child1, node.child2);
+
+            let area = node.aabb.perimeter();
+            let combined_area = node.aabb.union(&leaf_aabb).perimeter();
+
+            // Cost of making a new parent for this node and the leaf.
+            let cost = 2. * combined_area;
+            // Minimum cost of pushing the leaf further down.
+            let inheritance_cost = 2. * (combined_area - area);
+
+            let cost1 = self.descend_cost(child1, &leaf_aabb) + inheritance_cost;
+            let cost2 = self.descend_cost(child2, &leaf_aabb) + inheritance_cost;
+
+            if cost < cost1 && cost < cost2 {
+                break;
+            }
+
+            index = if cost1 < cost2 { child1 } else { child2 };
+        }
+
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate_node();
+        self.nodes[new_parent].parent = old_parent;
+        self.nodes[new_parent].aabb = leaf_aabb.union(&self.nodes[sibling].aabb);
+        self.nodes[new_parent].height = self.nodes[sibling].height + 1;
+        self.nodes[new_parent].child1 = sibling;
+        self.nodes[new_parent].child2 = leaf;
+        self.nodes[sibling].parent = new_parent;
+        self.nodes[leaf].parent = new_parent;
+
+        if old_parent == NULL_NODE {
+            self.root = new_parent;
+        } else if self.nodes[old_parent].child1 == sibling {
+            self.nodes[old_parent].child1 = new_parent;
+        } else {
+            self.nodes[old_parent].child2 = new_parent;
+        }
+
+        self.refit(self.nodes[leaf].parent);
+    }
+
+    fn descend_cost(&self, child: usize, leaf_aabb: &Aabb) -> f32 {
+        let child = &self.nodes[child];
+        let combined = child.aabb.union(leaf_aabb).perimeter();
+
+        if child.is_leaf() {
+            combined
+        } else {
+            combined - child.aabb.perimeter()
+        }
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL_NODE;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        let grand_parent = self.nodes[parent].parent;
+        let sibling = if self.nodes[parent].child1 == leaf {
+            self.nodes[parent].child2
+        } else {
+            self.nodes[parent].child1
+        };
+
+        if grand_parent == NULL_NODE {
+            self.root = sibling;
+            self.nodes[sibling].parent = NULL_NODE;
+            self.free_node(parent);
+            return;
+        }
+
+        // Replace the parent with the sibling.
+        if self.nodes[grand_parent].child1 == parent {
+            self.nodes[grand_parent].child1 = sibling;
+        } else {
+            self.nodes[grand_parent].child2 = sibling;
+        }
+        self.nodes[sibling].parent = grand_parent;
+        self.free_node(parent);
+
+        self.refit(grand_parent);
+    }
+
+    /// Walks from `index` to the root, rebalancing and fixing up heights and AABBs.
+    fn refit(&mut self, mut index: usize) {
+        while index != NULL_NODE {
+            index = self.balance(index);
+
+            let child1 = self.nodes[index].child1;
+            let child2 = self.nodes[index].child2;
+            self.nodes[index].height = 1 + self.nodes[child1].height.max(self.nodes[child2].height);
+            self.nodes[index].aabb = self.nodes[child1].aabb.union(&self.nodes[child2].aabb);
+
+            index = self.nodes[index].parent;
+        }
+    }
+
+    /// Rotates `a` left or right if its children's heights differ by more than one, returning the node that
+    /// took its place.
+    fn balance(&mut self, a: usize) -> usize {
+        if self.nodes[a].is_leaf() || self.nodes[a].height < 2 {
+            return a;
+        }
+
+        let b = self.nodes[a].child1;
+        let c = self.nodes[a].child2;
+        let balance = self.nodes[c].height - self.nodes[b].height;
+
+        if balance > 1 {
+            self.rotate(a, c, b)
+        } else if balance < -1 {
+            self.rotate(a, b, c)
+        } else {
+            a
+        }
+    }
+
+    /// Promotes `up`, a child of `a`, to take `a`'s place. `a` keeps `other` and the shorter of `up`'s children.
+    fn rotate(&mut self, a: usize, up: usize, other: usize) -> usize {
+        let f = self.nodes[up].child1;
+        let g = self.nodes[up].child2;
+
+        self.nodes[up].child1 = a;
+        self.nodes[up].parent = self.nodes[a].parent;
+        self.nodes[a].parent = up;
+
+        let parent = self.nodes[up].parent;
+        if parent == NULL_NODE {
+            self.root = up;
+        } else if self.nodes[parent].child1 == a {
+            self.nodes[parent].child1 = up;
+        } else {
+            self.nodes[parent].child2 = up;
+        }
+
+        let (taller, shorter) = if self.nodes[f].height > self.nodes[g].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+
+        self.nodes[up].child2 = taller;
+        if self.nodes[a].child1 == up {
+            self.nodes[a].child1 = shorter;
+        } else {
+            self.nodes[a].child2 = shorter;
+        }
+        self.nodes[shorter].parent = a;
+
+        self.nodes[a].aabb = self.nodes[other].aabb.union(&self.nodes[shorter].aabb);
+        self.nodes[up].aabb = self.nodes[a].aabb.union(&self.nodes[taller].aabb);
+        self.nodes[a].height = 1 + self.nodes[other].height.max(self.nodes[shorter].height);
+        self.nodes[up].height = 1 + self.nodes[a].height.max(self.nodes[taller].height);
+
+        up
+    }
+}
+
+impl Broadphase for DynamicTree {
+    fn create_proxy(&mut self, aabb: Aabb, user_data: usize) -> ProxyId {
+        let leaf = self.allocate_node();
+        self.nodes[leaf].aabb = aabb.fattened(AABB_MARGIN);
+        self.nodes[leaf].user_data = user_data;
+        self.nodes[leaf].height = 0;
+
+        self.insert_leaf(leaf);
+        ProxyId(leaf)
+    }
+
+    fn destroy_proxy(&mut self, proxy: ProxyId) {
+        debug_assert!(self.nodes[proxy.0].is_leaf());
+
+        self.remove_leaf(proxy.0);
+        self.free_node(proxy.0);
+    }
+
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: Aabb, displacement: Vec2) -> bool {
+        let leaf = proxy.0;
+        if self.nodes[leaf].aabb.contains(&aabb) {
+            return false;
+        }
+
+        self.remove_leaf(leaf);
+        self.nodes[leaf].aabb = aabb
+            .fattened(AABB_MARGIN)
+            .swept(displacement * AABB_MULTIPLIER);
+        self.insert_leaf(leaf);
+
+        true
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(usize) -> bool) {
+        let mut stack = vec![self.root];
+
+        while let Some(index) = stack.pop() {
+            if index == NULL_NODE {
+                continue;
+            }
+
+            let node = &self.nodes[index];
+            if !node.aabb.overlaps(aabb) {
+                continue;
+            }
+
+            if node.is_leaf() {
+                if !callback(node.user_data) {
+                    return;
+                }
+            } else {
+                stack.push(node.child1);
+                stack.push(node.child2);
+            }
+        }
+    }
+
+    fn raycast(&self, ray: &Ray, mut max_t: f32, callback: &mut dyn FnMut(usize) -> f32) {
+        let mut stack = vec![self.root];
+
+        while let Some(index) = stack.pop() {
+            if index == NULL_NODE {
+                continue;
+            }
+
+            let node = &self.nodes[index];
+            if node.aabb.raycast(ray, max_t).is_none() {
+                continue;
+            }
+
+            if node.is_leaf() {
+                max_t = callback(node.user_data);
+                if max_t < 0. {
+                    return;
+                }
+            } else {
+                stack.push(node.child1);
+                stack.push(node.child2);
+            }
+        }
+    }
+
+    fn find_pairs(&mut self, pairs: &mut Vec<(usize, usize)>) {
+        let mut stack = Vec::new();
+
+        for (leaf, node) in self.nodes.iter().enumerate() {
+            if node.height != 0 {
+                continue;
+            }
+
+            stack.push(self.root);
+            while let Some(index) = stack.pop() {
+                let other = &self.nodes[index];
+                if !other.aabb.overlaps(&node.aabb) {
+                    continue;
+                }
+
+                if other.is_leaf() {
+                    // Each pair is met from both of its leaves, only keep one of them.
+                    if index > leaf {
+                        let (a, b) = (node.user_data, other.user_data);
+                        pairs.push((a.min(b), a.max(b)));
+                    }
+                } else {
+                    stack.push(other.child1);
+                    stack.push(other.child2);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use crate::math::{aabb::Aabb, ray::Ray, vec2::Vec2};
+
+pub mod brute_force;
+pub mod dynamic_tree;
+pub mod spatial_hash;
+pub mod sweep_and_prune;
+
+// Fat AABBs are grown by this much, so small movements don't have to update the broadphase.
+pub const AABB_MARGIN: f32 = 0.1;
+// How many steps of predicted motion a fat AABB is stretched by when it is updated.
+pub const AABB_MULTIPLIER: f32 = 2.;
+
+/// Handle to a proxy in a broadphase, stays valid until the proxy is destroyed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProxyId(pub usize);
+
+/// Finds pairs of bodies that might be touching, so the narrowphase only has to run on those.
+///
+/// Proxies hold a fattened copy of a body's AABB and a `user_data` index, in `Simulation` the index of the body.
+pub trait Broadphase {
+    /// Starts tracking `aabb`, which is stored fattened.
+    fn create_proxy(&mut self, aabb: Aabb, user_data: usize) -> ProxyId;
+
+    fn destroy_proxy(&mut self, proxy: ProxyId);
+
+    /// Updates the bounds of a proxy that moved by `displacement` since the last call.
+    ///
+    /// Returns false without doing anything if `aabb` still fits in the proxy's fat AABB.
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: Aabb, displacement: Vec2) -> bool;
+
+    /// Calls `callback` with the user data of every proxy whose fat AABB overlaps `aabb`.
+    /// Returning false from the callback stops the query.
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(usize) -> bool);
+
+    /// Calls `callback` with the user data of every proxy whose fat AABB `ray` crosses before `max_t`.
+    ///
+    /// The callback returns the `max_t` to carry on with, so a hit can shorten the ray and skip everything
+    /// behind it. Returning a negative value stops the cast.
+    fn raycast(&self, ray: &Ray, max_t: f32, callback: &mut dyn FnMut(usize) -> f32);
+
+    /// Appends every pair of proxies whose fat AABBs overlap, as user data with the lowest first.
+    fn find_pairs(&mut self, pairs: &mut Vec<(usize, usize)>);
+
+    /// Catches up on work put off while proxies were changing, so the queries and raycasts that follow don't
+    /// have to fall back to checking every proxy. `Simulation` calls it at the end of every step.
+    fn commit(&mut self) {}
+}
@@ -0,0 +1,296 @@
+use crate::math::{aabb::Aabb, ray::Ray, vec2::Vec2};
+
+use super::{Broadphase, ProxyId, AABB_MARGIN, AABB_MULTIPLIER};
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    cell: (i32, i32),
+    proxy: usize,
+}
+
+/// Uniform grid of square cells, hashed into a table that is rebuilt whenever a proxy moved.
+///
+/// Meant for lots of bodies of about the same size, with `cell_size` a little larger than one of them. A body
+/// much larger than a cell is added to every cell it touches. Once the table has grown to fit the scene,
+/// rebuilding it doesn't allocate.
+pub struct SpatialHash {
+    /// Width and height of a cell, read on every rebuild.
+    pub cell_size: f32,
+    // `None` marks a destroyed proxy whose slot can be reused.
+    proxies: Vec<Option<(Aabb, usize)>>,
+    free: Vec<usize>,
+    // Where every bucket of the table starts in `entries`, followed by the number of entries.
+    buckets: Vec<usize>,
+    // Every cell covered by every proxy, grouped by bucket.
+    entries: Vec<Entry>,
+    // Around every proxy, `None` if there are none.
+    bounds: Option<Aabb>,
+    // Set when the table no longer matches the proxies.
+    dirty: bool,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            proxies: vec![],
+            free: vec![],
+            buckets: vec![0],
+            entries: vec![],
+            bounds: None,
+            // Nothing built yet.
+            dirty: true,
+        }
+    }
+
+    fn cell(&self, x: f32, y: f32) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Every cell `aabb` touches.
+    fn cells(&self, aabb: &Aabb) -> impl Iterator<Item = (i32, i32)> {
+        let (min_x, min_y) = self.cell(aabb.min.x, aabb.min.y);
+        let (max_x, max_y) = self.cell(aabb.max.x, aabb.max.y);
+
+        (min_y..=max_y).flat_map(move |y| (min_x..=max_x).map(move |x| (x, y)))
+    }
+
+    /// The one cell shared by two overlapping boxes that reports them, so pairs spanning several cells are
+    /// only found once.
+    fn owner(&self, a: &Aabb, b: &Aabb) -> (i32, i32) {
+        self.cell(a.min.x.max(b.min.x), a.min.y.max(b.min.y))
+    }
+
+    fn bucket(&self, (x, y): (i32, i32)) -> usize {
+        let hash = (x as u32).wrapping_mul(73_856_093) ^ (y as u32).wrapping_mul(19_349_663);
+        hash as usize & (self.buckets.len() - 2)
+    }
+
+    /// Counting sort of every covered cell into its bucket.
+    fn rebuild(&mut self) {
+        let table_size = (self.proxies.len() * 2).next_power_of_two().max(64);
+        self.buckets.clear();
+        self.buckets.resize(table_size + 1, 0);
+
+        self.bounds = None;
+        for (aabb, _) in self.proxies.iter().flatten() {
+            self.bounds = Some(self.bounds.map_or(*aabb, |bounds| bounds.union(aabb)));
+
+            for cell in self.cells(aabb) {
+                let bucket = self.bucket(cell);
+                self.buckets[bucket] += 1;
+            }
+        }
+
+        // Turn the counts into where every bucket ends, then count back down while filling it in, which leaves
+        // where it starts.
+        let mut total = 0;
+        for bucket in &mut self.buckets {
+            total += *bucket;
+            *bucket = total;
+        }
+
+        self.entries.clear();
+        self.entries.resize(total, Entry::default());
+
+        for (proxy, slot) in self.proxies.iter().enumerate() {
+            let Some((aabb, _)) = slot else {
+                continue;
+            };
+
+            for cell in self.cells(aabb) {
+                let bucket = self.bucket(cell);
+                self.buckets[bucket] -= 1;
+                self.entries[self.buckets[bucket]] = Entry { cell, proxy };
+            }
+        }
+
+        self.dirty = false;
+    }
+
+    fn proxy(&self, index: usize) -> &(Aabb, usize) {
+        self.proxies[index].as_ref().unwrap()
+    }
+}
+
+impl Broadphase for SpatialHash {
+    fn create_proxy(&mut self, aabb: Aabb, user_data: usize) -> ProxyId {
+        let proxy = Some((aabb.fattened(AABB_MARGIN), user_data));
+        self.dirty = true;
+
+        match self.free.pop() {
+            Some(index) => {
+                self.proxies[index] = proxy;
+                ProxyId(index)
+            }
+            None => {
+                self.proxies.push(proxy);
+                ProxyId(self.proxies.len() - 1)
+            }
+        }
+    }
+
+    fn destroy_proxy(&mut self, proxy: ProxyId) {
+        self.proxies[proxy.0] = None;
+        self.free.push(proxy.0);
+        self.dirty = true;
+    }
+
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: Aabb, displacement: Vec2) -> bool {
+        let Some((fat_aabb, _)) = &mut self.proxies[proxy.0] else {
+            return false;
+        };
+        if fat_aabb.contains(&aabb) {
+            return false;
+        }
+
+        *fat_aabb = aabb
+            .fattened(AABB_MARGIN)
+            .swept(displacement * AABB_MULTIPLIER);
+        self.dirty = true;
+        true
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(usize) -> bool) {
+        // The table is only rebuilt by `find_pairs` and `commit`, until then check every proxy.
+        if self.dirty {
+            for (fat_aabb, user_data) in self.proxies.iter().flatten() {
+                if fat_aabb.overlaps(aabb) && !callback(*user_data) {
+                    return;
+                }
+            }
+            return;
+        }
+
+        for cell in self.cells(aabb) {
+            let bucket = self.bucket(cell);
+
+            for entry in &self.entries[self.buckets[bucket]..self.buckets[bucket + 1]] {
+                let (fat_aabb, user_data) = self.proxy(entry.proxy);
+
+                if entry.cell == cell
+                    && fat_aabb.overlaps(aabb)
+                    && self.owner(fat_aabb, aabb) == cell
+                    && !callback(*user_data)
+                {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn raycast(&self, ray: &Ray, mut max_t: f32, callback: &mut dyn FnMut(usize) -> f32) {
+        if self.dirty {
+            for (fat_aabb, user_data) in self.proxies.iter().flatten() {
+                if fat_aabb.raycast(ray, max_t).is_some() {
+                    max_t = callback(*user_data);
+                    if max_t < 0. {
+                        return;
+                    }
+                }
+            }
+            return;
+        }
+
+        // Walks the cells the ray passes through in order, from where it reaches the first proxy to where it
+        // leaves the last. A proxy covering several of them is only reported from the first.
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+        let Some(mut enter) = bounds.raycast(ray, max_t) else {
+            return;
+        };
+        let (min_cell, max_cell) = (
+            self.cell(bounds.min.x, bounds.min.y),
+            self.cell(bounds.max.x, bounds.max.y),
+        );
+        let start = ray.at(enter);
+        let start = self.cell(start.x, start.y);
+        // Rounding can put the start just outside the bounds.
+        let mut cell = (
+            start.0.clamp(min_cell.0, max_cell.0),
+            start.1.clamp(min_cell.1, max_cell.1),
+        );
+        let axis = |origin: f32, direction: f32, cell: i32| {
+            if direction == 0. {
+                return (0, f32::INFINITY, f32::INFINITY);
+            }
+
+            let step = direction.signum() as i32;
+            let boundary = (cell + (step > 0) as i32) as f32 * self.cell_size;
+            (
+                step,
+                (boundary - origin) / direction,
+                self.cell_size / direction.abs(),
+            )
+        };
+        let (step_x, mut next_x, delta_x) = axis(ray.origin.x, ray.direction.x, cell.0);
+        let (step_y, mut next_y, delta_y) = axis(ray.origin.y, ray.direction.y, cell.1);
+
+        let mut reported = Vec::new();
+        while enter <= max_t
+            && (min_cell.0..=max_cell.0).contains(&cell.0)
+            && (min_cell.1..=max_cell.1).contains(&cell.1)
+        {
+            let bucket = self.bucket(cell);
+
+            for entry in &self.entries[self.buckets[bucket]..self.buckets[bucket + 1]] {
+                if entry.cell != cell || reported.contains(&entry.proxy) {
+                    continue;
+                }
+
+                let (fat_aabb, user_data) = self.proxy(entry.proxy);
+                if fat_aabb.raycast(ray, max_t).is_some() {
+                    reported.push(entry.proxy);
+                    max_t = callback(*user_data);
+                    if max_t < 0. {
+                        return;
+                    }
+                }
+            }
+
+            if next_x < next_y {
+                cell.0 += step_x;
+                enter = next_x;
+                next_x += delta_x;
+            } else {
+                cell.1 += step_y;
+                enter = next_y;
+                next_y += delta_y;
+            }
+        }
+    }
+
+    fn find_pairs(&mut self, pairs: &mut Vec<(usize, usize)>) {
+        self.commit();
+
+        for bucket in self.buckets.windows(2) {
+            let entries = &self.entries[bucket[0]..bucket[1]];
+
+            for (i, entry) in entries.iter().enumerate() {
+                let (aabb_a, a) = self.proxy(entry.proxy);
+
+                for other in &entries[(i + 1)..] {
+                    // Different cells that happen to share a bucket.
+                    if other.cell != entry.cell {
+                        continue;
+                    }
+
+                    let (aabb_b, b) = self.proxy(other.proxy);
+                    if aabb_a.overlaps(aabb_b) && self.owner(aabb_a, aabb_b) == entry.cell {
+                        pairs.push((*a.min(b), *a.max(b)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn commit(&mut self) {
+        if self.dirty {
+            self.rebuild();
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::math::{aabb::Aabb, ray::Ray, vec2::Vec2};
+
+use super::{Broadphase, ProxyId, AABB_MARGIN, AABB_MULTIPLIER};
+
+/// Keeps proxies sorted by the left edge of their fat AABB and sweeps along x for overlaps.
+///
+/// Bodies barely move between steps, so the order is fixed up with an insertion sort that is close to linear.
+/// Works best with many bodies of a similar size that aren't stacked up along x.
+#[derive(Default)]
+pub struct SweepAndPrune {
+    // `None` marks a destroyed proxy whose slot can be reused.
+    proxies: Vec<Option<(Aabb, usize)>>,
+    free: Vec<usize>,
+    // Slots of the live proxies, by `min.x`.
+    order: Vec<usize>,
+    // Set when a proxy was created or moved since `order` was last sorted.
+    unsorted: bool,
+}
+
+impl SweepAndPrune {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn aabb(&self, index: usize) -> &Aabb {
+        &self.proxies[index].as_ref().unwrap().0
+    }
+
+    fn sort(&mut self) {
+        for i in 1..self.order.len() {
+            let index = self.order[i];
+            let min_x = self.aabb(index).min.x;
+
+            let mut j = i;
+            while j > 0 && self.aabb(self.order[j - 1]).min.x > min_x {
+                self.order[j] = self.order[j - 1];
+                j -= 1;
+            }
+            self.order[j] = index;
+        }
+
+        self.unsorted = false;
+    }
+}
+
+impl Broadphase for SweepAndPrune {
+    fn create_proxy(&mut self, aabb: Aabb, user_data: usize) -> ProxyId {
+        let proxy = Some((aabb.fattened(AABB_MARGIN), user_data));
+
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.proxies[index] = proxy;
+                index
+            }
+            None => {
+                self.proxies.push(proxy);
+                self.proxies.len() - 1
+            }
+        };
+
+        self.order.push(index);
+        self.unsorted = true;
+        ProxyId(index)
+    }
+
+    fn destroy_proxy(&mut self, proxy: ProxyId) {
+        self.proxies[proxy.0] = None;
+        self.free.push(proxy.0);
+        self.order.retain(|&index| index != proxy.0);
+    }
+
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: Aabb, displacement: Vec2) -> bool {
+        let Some((fat_aabb, _)) = &mut self.proxies[proxy.0] else {
+            return false;
+        };
+        if fat_aabb.contains(&aabb) {
+            return false;
+        }
+
+        *fat_aabb = aabb
+            .fattened(AABB_MARGIN)
+            .swept(displacement * AABB_MULTIPLIER);
+        self.unsorted = true;
+        true
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(usize) -> bool) {
+        for &index in &self.order {
+            let (fat_aabb, user_data) = self.proxies[index].as_ref().unwrap();
+
+            // Everything after this starts further right, unless a proxy moved since the last sort.
+            if fat_aabb.min.x > aabb.max.x && !self.unsorted {
+                return;
+            }
+
+            if fat_aabb.overlaps(aabb) && !callback(*user_data) {
+                return;
+            }
+        }
+    }
+
+    fn raycast(&self, ray: &Ray, mut max_t: f32, callback: &mut dyn FnMut(usize) -> f32) {
+        for (fat_aabb, user_data) in self.proxies.iter().flatten() {
+            if fat_aabb.raycast(ray, max_t).is_some() {
+                max_t = callback(*user_data);
+                if max_t < 0. {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn find_pairs(&mut self, pairs: &mut Vec<(usize, usize)>) {
+        self.commit();
+
+        for (i, &index) in self.order.iter().enumerate() {
+            let (aabb_a, a) = self.proxies[index].as_ref().unwrap();
+
+            for &other in &self.order[(i + 1)..] {
+                let (aabb_b, b) = self.proxies[other].as_ref().unwrap();
+                if aabb_b.min.x > aabb_a.max.x {
+                    break;
+                }
+
+                if aabb_a.min.y <= aabb_b.max.y && aabb_a.max.y >= aabb_b.min.y {
+                    pairs.push((*a.min(b), *a.max(b)));
+                }
+            }
+        }
+    }
+
+    fn commit(&mut self) {
+        if self.unsorted {
+            self.sort();
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    math::vec2::{Pos2, Vec2},
+    shapes::{circle::Circle, Shape, ShapeVariant},
+};
+
+use super::{epa, gjk};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Distance {
+    /// Gap between the shapes, or minus how deep they overlap.
+    pub distance: f32,
+    /// Point on `a` closest to `b`, or deepest inside it when they overlap.
+    pub point_a: Pos2,
+    /// Point on `b` closest to `a`, or deepest inside it when they overlap.
+    pub point_b: Pos2,
+    /// Direction from `a` to `b`, the way `b` would have to move to separate from `a` when they overlap.
+    /// When any way is as good as another, like for two circles with the same center, it is the x axis.
+    pub normal: Vec2,
+}
+
+/// Signed distance between two shapes and their closest points.
+///
+/// Circles are measured from their center and then shrunk by their radius, which keeps their curve exact
+/// where GJK and EPA could only approximate it.
+pub fn distance(a: &ShapeVariant, b: &ShapeVariant) -> Distance {
+    match (a, b) {
+        (ShapeVariant::Circle(c1), ShapeVariant::Circle(c2)) => circle_distance(c1, c2),
+        (ShapeVariant::Circle(c1), ShapeVariant::Polygon(p2)) => {
+            core_distance(&center(c1), c1.radius, p2, 0.)
+        }
+        (ShapeVariant::Polygon(p1), ShapeVariant::Circle(c2)) => {
+            core_distance(p1, 0., &center(c2), c2.radius)
+        }
+        (ShapeVariant::Polygon(p1), ShapeVariant::Polygon(p2)) => core_distance(p1, 0., p2, 0.),
+    }
+}
+
+fn circle_distance(a: &Circle, b: &Circle) -> Distance {
+    let offset = b.position - a.position;
+    let length = offset.length();
+    let normal = if length > 0. {
+        offset / length
+    } else {
+        Vec2::with(1., 0.)
+    };
+
+    Distance {
+        distance: length - a.radius - b.radius,
+        point_a: a.position + normal * a.radius,
+        point_b: b.position - normal * b.radius,
+        normal,
+    }
+}
+
+/// A circle shrunk down to its center.
+fn center(circle: &Circle) -> Circle {
+    Circle::new(circle.position, 0.)
+}
+
+/// Distance between `a` and `b` grown by `radius_a` and `radius_b`.
+fn core_distance(a: &impl Shape, radius_a: f32, b: &impl Shape, radius_b: f32) -> Distance {
+    let radius = radius_a + radius_b;
+    let closest = gjk::closest_points(a, b);
+
+    if !closest.overlapping() {
+        let normal = (closest.point_b - closest.point_a) / closest.distance;
+
+        return Distance {
+            distance: closest.distance - radius,
+            point_a: closest.point_a + normal * radius_a,
+            point_b: closest.point_b - normal * radius_b,
+            normal,
+        };
+    }
+
+    match epa::penetration(a, b, &closest.simplex) {
+        Some(penetration) => Distance {
+            distance: -penetration.depth - radius,
+            point_a: penetration.point_a + penetration.normal * radius_a,
+            point_b: penetration.point_b - penetration.normal * radius_b,
+            normal: penetration.normal,
+        },
+        // The cores only touch, so there is no direction to go by.
+        None => {
+            let normal = Vec2::with(1., 0.);
+            Distance {
+                distance: -radius,
+                point_a: closest.point_a + normal * radius_a,
+                point_b: closest.point_b - normal * radius_b,
+                normal,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::polygon::Polygon;
+
+    fn circle(x: f32, y: f32, radius: f32) -> ShapeVariant {
+        ShapeVariant::Circle(Circle::new(Pos2::at(x, y), radius))
+    }
+
+    fn rectangle(x: f32, y: f32, width: f32, height: f32) -> ShapeVariant {
+        ShapeVariant::Polygon(Polygon::rectangle(Pos2::at(x, y), width, height))
+    }
+
+    fn assert_finite(d: &Distance) {
+        for value in [
+            d.distance,
+            d.point_a.x,
+            d.point_a.y,
+            d.point_b.x,
+            d.point_b.y,
+        ] {
+            assert!(value.is_finite(), "{d:?}");
+        }
+        assert!((d.normal.length() - 1.).abs() < 1e-4, "{d:?}");
+    }
+
+    #[test]
+    fn separated_circles() {
+        let d = distance(&circle(0., 0., 1.), &circle(4., 0., 0.5));
+        assert_finite(&d);
+        assert!((d.distance - 2.5).abs() < 1e-5);
+        assert!((d.point_a.x - 1.).abs() < 1e-5 && (d.point_b.x - 3.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn concentric_circles() {
+        let d = distance(&circle(0., 0., 1.), &circle(0., 0., 1.));
+        assert_finite(&d);
+        assert!((d.distance + 2.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn circle_centered_on_polygon_corner() {
+        let d = distance(&circle(1., 1., 0.5), &rectangle(0., 0., 2., 2.));
+        assert_finite(&d);
+        assert!((d.distance + 0.5).abs() < 1e-4, "{d:?}");
+    }
+
+    #[test]
+    fn circle_centered_on_polygon_edge() {
+        let d = distance(&rectangle(0., 0., 2., 2.), &circle(1., 0., 0.5));
+        assert_finite(&d);
+        assert!((d.distance + 0.5).abs() < 1e-4, "{d:?}");
+    }
+
+    #[test]
+    fn separated_polygons() {
+        let d = distance(&rectangle(0., 0., 2., 2.), &rectangle(5., 0.5, 2., 2.));
+        assert_finite(&d);
+        assert!((d.distance - 3.).abs() < 1e-4);
+        assert!((d.normal.x - 1.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn overlapping_polygons() {
+        let d = distance(&rectangle(0., 0., 2., 2.), &rectangle(1.5, 0., 2., 2.));
+        assert_finite(&d);
+        assert!((d.distance + 0.5).abs() < 1e-4, "{d:?}");
+        assert!((d.normal.x - 1.).abs() < 1e-4, "{d:?}");
+    }
+}
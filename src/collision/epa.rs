@@ -0,0 +1,157 @@
+use crate::{
+    math::vec2::{Pos2, Vec2},
+    shapes::Shape,
+};
+
+use super::gjk::{Simplex, SimplexVertex};
+
+const MAX_ITERATIONS: usize = 32;
+// How close the polytope has to get to the boundary of `a - b` before the depth is accepted.
+const TOLERANCE: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Penetration {
+    /// Direction from `a` to `b` that separates them the quickest.
+    pub normal: Vec2,
+    pub depth: f32,
+    /// Deepest point of `a` inside `b`.
+    pub point_a: Pos2,
+    /// Deepest point of `b` inside `a`.
+    pub point_b: Pos2,
+}
+
+/// Expands the simplex GJK ended with into a polygon inside `a - b` until it finds the boundary closest to the
+/// origin, the smallest translation that separates the shapes.
+///
+/// Returns `None` if the shapes only touch, with nothing to push apart.
+pub fn penetration(a: &impl Shape, b: &impl Shape, simplex: &Simplex) -> Option<Penetration> {
+    let mut polytope: Vec<SimplexVertex> = simplex.vertices().to_vec();
+
+    // GJK stops early when the origin lies on a vertex or edge, grow those into a triangle.
+    if polytope.len() == 1 {
+        // A vertex on the origin gives no direction to search in.
+        let direction = if polytope[0].point.length_squared() > 0. {
+            -polytope[0].point
+        } else {
+            Vec2::with(1., 0.)
+        };
+        polytope.push(SimplexVertex::support(a, b, direction));
+        if (polytope[1].point - polytope[0].point).length_squared() == 0. {
+            polytope[1] = SimplexVertex::support(a, b, -direction);
+        }
+    }
+    if polytope.len() == 2 {
+        let edge = polytope[1].point - polytope[0].point;
+        // Both shapes are single points, like circles shrunk to their centers.
+        if edge.length_squared() == 0. {
+            return None;
+        }
+
+        let mut vertex = SimplexVertex::support(a, b, edge.perpendicular());
+
+        if (vertex.point - polytope[0].point).cross(edge).abs() < TOLERANCE {
+            vertex = SimplexVertex::support(a, b, edge.orthogonal());
+        }
+        polytope.push(vertex);
+    }
+
+    let area = (polytope[1].point - polytope[0].point).cross(polytope[2].point - polytope[0].point);
+    if area.abs() < TOLERANCE * TOLERANCE {
+        return None;
+    }
+    // Wind the polytope so `orthogonal` gives the outward normal of every edge.
+    if area < 0. {
+        polytope.swap(1, 2);
+    }
+
+    let mut closest = closest_edge(&polytope);
+    for _ in 0..MAX_ITERATIONS {
+        let vertex = SimplexVertex::support(a, b, closest.normal);
+
+        if vertex.point.dot(closest.normal) - closest.distance < TOLERANCE {
+            break;
+        }
+
+        polytope.insert(closest.index + 1, vertex);
+        closest = closest_edge(&polytope);
+    }
+
+    let v1 = polytope[closest.index];
+    let v2 = polytope[(closest.index + 1) % polytope.len()];
+
+    // Where the origin projects onto the edge.
+    let edge = v2.point - v1.point;
+    let t = (-v1.point.dot(edge) / edge.length_squared()).clamp(0., 1.);
+
+    Some(Penetration {
+        normal: closest.normal,
+        depth: closest.distance,
+        point_a: v1.point_a + (v2.point_a - v1.point_a) * t,
+        point_b: v1.point_b + (v2.point_b - v1.point_b) * t,
+    })
+}
+
+struct Edge {
+    index: usize,
+    normal: Vec2,
+    distance: f32,
+}
+
+fn closest_edge(polytope: &[SimplexVertex]) -> Edge {
+    let mut closest = Edge {
+        index: 0,
+        normal: Vec2::ZERO,
+        distance: f32::MAX,
+    };
+
+    for index in 0..polytope.len() {
+        let v1 = polytope[index].point;
+        let v2 = polytope[(index + 1) % polytope.len()].point;
+
+        let normal = (v2 - v1).orthogonal().normalized();
+        let distance = normal.dot(v1);
+
+        if distance < closest.distance {
+            closest = Edge {
+                index,
+                normal,
+                distance,
+            };
+        }
+    }
+
+    closest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collision::gjk,
+        shapes::{circle::Circle, polygon::Polygon},
+    };
+
+    #[test]
+    fn circle_overlapping_polygon() {
+        let wall = Polygon::rectangle(Pos2::at(5., 0.), 0.05, 6.);
+        let circle = Circle::new(Pos2::at(4.975 - 0.4, 1.), 0.5);
+
+        let closest = gjk::closest_points(&circle, &wall);
+        let penetration = penetration(&circle, &wall, &closest.simplex).unwrap();
+
+        assert!((penetration.depth - 0.1).abs() < 1e-3, "{penetration:?}");
+        assert!((penetration.normal.x - 1.).abs() < 1e-3, "{penetration:?}");
+    }
+
+    #[test]
+    fn overlapping_polygons() {
+        let a = Polygon::rectangle(Pos2::at(0., 0.), 2., 2.);
+        let b = Polygon::rectangle(Pos2::at(0.5, 1.8), 2., 2.);
+
+        let closest = gjk::closest_points(&a, &b);
+        let penetration = penetration(&a, &b, &closest.simplex).unwrap();
+
+        assert!((penetration.depth - 0.2).abs() < 1e-4, "{penetration:?}");
+        assert!((penetration.normal.y - 1.).abs() < 1e-4, "{penetration:?}");
+    }
+}
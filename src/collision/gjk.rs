@@ -0,0 +1,331 @@
+use crate::{
+    math::vec2::{Pos2, Vec2},
+    shapes::Shape,
+};
+
+const MAX_ITERATIONS: usize = 32;
+// Stop once a new support point gets closer to the origin than this fraction of the distance. Curved shapes
+// never converge exactly.
+const RELATIVE_TOLERANCE: f32 = 1e-5;
+// Below this distance the shapes are considered to be touching.
+const TOUCHING_DISTANCE: f32 = 1e-6;
+// A triangle flatter than this, as the sine of the angle between two of its edges, has no inside to hold the
+// origin. Curved shapes make them out of support points a hair apart.
+const DEGENERATE_SINE: f32 = 1e-4;
+
+/// A point on the boundary of the Minkowski difference `a - b`, with the support points of each shape it came
+/// from.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimplexVertex {
+    pub point_a: Pos2,
+    pub point_b: Pos2,
+    pub point: Vec2,
+    // Barycentric weight of the vertex in the simplex's closest point to the origin.
+    weight: f32,
+}
+
+impl SimplexVertex {
+    /// The point of `a - b` furthest along `direction`.
+    pub fn support(a: &impl Shape, b: &impl Shape, direction: Vec2) -> SimplexVertex {
+        let point_a = a.support(direction);
+        let point_b = b.support(-direction);
+
+        SimplexVertex {
+            point_a,
+            point_b,
+            point: point_a - point_b,
+            weight: 1.,
+        }
+    }
+}
+
+/// Up to three vertices of `a - b`, reduced to the feature closest to the origin.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Simplex {
+    vertices: [SimplexVertex; 3],
+    count: usize,
+}
+
+impl Simplex {
+    pub fn vertices(&self) -> &[SimplexVertex] {
+        &self.vertices[..self.count]
+    }
+
+    fn push(&mut self, vertex: SimplexVertex) {
+        self.vertices[self.count] = vertex;
+        self.count += 1;
+    }
+
+    fn closest_point(&self) -> Vec2 {
+        self.vertices()
+            .iter()
+            .fold(Vec2::ZERO, |sum, v| sum + v.point * v.weight)
+    }
+
+    /// The points on `a` and `b` that make up the closest point.
+    fn witness_points(&self) -> (Pos2, Pos2) {
+        let first = self.vertices[0];
+
+        self.vertices()[1..]
+            .iter()
+            .fold((first.point_a, first.point_b), |(a, b), v| {
+                (
+                    a + (v.point_a - first.point_a) * v.weight,
+                    b + (v.point_b - first.point_b) * v.weight,
+                )
+            })
+    }
+
+    /// Drops the vertices that don't contribute to the closest point to the origin and weights the rest.
+    fn reduce(&mut self) {
+        match self.count {
+            1 => self.vertices[0].weight = 1.,
+            2 => self.reduce_segment(),
+            3 => self.reduce_triangle(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn reduce_segment(&mut self) {
+        let [w1, w2, _] = self.vertices.map(|v| v.point);
+        let e12 = w2 - w1;
+
+        let d12_1 = w2.dot(e12);
+        let d12_2 = -w1.dot(e12);
+
+        if d12_2 <= 0. {
+            self.keep_vertex(0);
+        } else if d12_1 <= 0. {
+            self.keep_vertex(1);
+        } else {
+            self.keep_segment(1, d12_1, d12_2);
+        }
+    }
+
+    fn reduce_triangle(&mut self) {
+        let [w1, w2, w3] = self.vertices.map(|v| v.point);
+
+        let e12 = w2 - w1;
+        let d12_1 = w2.dot(e12);
+        let d12_2 = -w1.dot(e12);
+
+        let e13 = w3 - w1;
+        let d13_1 = w3.dot(e13);
+        let d13_2 = -w1.dot(e13);
+
+        let e23 = w3 - w2;
+        let d23_1 = w3.dot(e23);
+        let d23_2 = -w2.dot(e23);
+
+        // Signed areas of the triangles the origin makes with each edge.
+        let n123 = e12.cross(e13);
+        let d123_1 = n123 * w2.cross(w3);
+        let d123_2 = n123 * w3.cross(w1);
+        let d123_3 = n123 * w1.cross(w2);
+
+        if d12_2 <= 0. && d13_2 <= 0. {
+            self.keep_vertex(0);
+        } else if d12_1 > 0. && d12_2 > 0. && d123_3 <= 0. {
+            self.keep_segment(1, d12_1, d12_2);
+        } else if d13_1 > 0. && d13_2 > 0. && d123_2 <= 0. {
+            self.keep_segment(2, d13_1, d13_2);
+        } else if d12_1 <= 0. && d23_2 <= 0. {
+            self.keep_vertex(1);
+        } else if d13_1 <= 0. && d23_1 <= 0. {
+            self.keep_vertex(2);
+        } else if d23_1 > 0. && d23_2 > 0. && d123_1 <= 0. {
+            self.vertices[0] = self.vertices[2];
+            self.keep_segment(1, d23_2, d23_1);
+        } else if n123.abs() <= DEGENERATE_SINE * (e12.length() * e13.length()) {
+            // Too flat to tell which side of its edges the origin is on, settle for the closest edge.
+            self.keep_closest_edge();
+        } else {
+            // The origin is inside the triangle.
+            let inv_sum = (d123_1 + d123_2 + d123_3).recip();
+            self.vertices[0].weight = d123_1 * inv_sum;
+            self.vertices[1].weight = d123_2 * inv_sum;
+            self.vertices[2].weight = d123_3 * inv_sum;
+        }
+    }
+
+    fn keep_closest_edge(&mut self) {
+        let [v1, v2, v3] = self.vertices;
+
+        *self = [(v1, v2), (v1, v3), (v2, v3)]
+            .map(|(first, second)| {
+                let mut edge = Simplex {
+                    vertices: [first, second, second],
+                    count: 2,
+                };
+                edge.reduce_segment();
+                edge
+            })
+            .into_iter()
+            .min_by(|e1, e2| {
+                let d1 = e1.closest_point().length_squared();
+                let d2 = e2.closest_point().length_squared();
+                d1.total_cmp(&d2)
+            })
+            .unwrap();
+    }
+
+    fn keep_vertex(&mut self, index: usize) {
+        self.vertices[0] = self.vertices[index];
+        self.vertices[0].weight = 1.;
+        self.count = 1;
+    }
+
+    /// Keeps the segment from the first vertex to `other`, weighted by the opposite projections.
+    fn keep_segment(&mut self, other: usize, first_weight: f32, other_weight: f32) {
+        let inv_sum = (first_weight + other_weight).recip();
+
+        self.vertices[1] = self.vertices[other];
+        self.vertices[0].weight = first_weight * inv_sum;
+        self.vertices[1].weight = other_weight * inv_sum;
+        self.count = 2;
+    }
+
+    fn contains(&self, vertex: &SimplexVertex) -> bool {
+        self.vertices().iter().any(|v| {
+            (v.point_a - vertex.point_a).length_squared() == 0.
+                && (v.point_b - vertex.point_b).length_squared() == 0.
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClosestPoints {
+    /// Zero when the shapes touch or overlap.
+    pub distance: f32,
+    pub point_a: Pos2,
+    pub point_b: Pos2,
+    /// Where the search ended, a triangle around the origin when the shapes overlap.
+    pub simplex: Simplex,
+}
+
+impl ClosestPoints {
+    pub fn overlapping(&self) -> bool {
+        self.distance == 0.
+    }
+}
+
+/// Finds the closest points between two convex shapes with GJK, only using their support points.
+pub fn closest_points(a: &impl Shape, b: &impl Shape) -> ClosestPoints {
+    let mut direction = b.center() - a.center();
+    if direction.length_squared() == 0. {
+        direction = Vec2::with(1., 0.);
+    }
+
+    let mut simplex = Simplex::default();
+    simplex.push(SimplexVertex::support(a, b, -direction));
+    simplex.reduce();
+
+    for _ in 0..MAX_ITERATIONS {
+        let closest = simplex.closest_point();
+        let distance_squared = closest.length_squared();
+        if distance_squared < TOUCHING_DISTANCE * TOUCHING_DISTANCE {
+            break;
+        }
+
+        let vertex = SimplexVertex::support(a, b, -closest);
+
+        // The new vertex isn't getting any closer, `closest` is as good as it gets.
+        if distance_squared - closest.dot(vertex.point) <= RELATIVE_TOLERANCE * distance_squared
+            || simplex.contains(&vertex)
+        {
+            break;
+        }
+
+        let mut next = simplex;
+        next.push(vertex);
+        next.reduce();
+
+        if next.count == 3 {
+            simplex = next;
+            break;
+        }
+
+        // Rounding kept the new simplex from getting closer, so the old one is the best there is.
+        if next.closest_point().length_squared() >= distance_squared {
+            break;
+        }
+
+        simplex = next;
+    }
+
+    let (point_a, point_b) = simplex.witness_points();
+    let distance = if simplex.count == 3 {
+        0.
+    } else {
+        let distance = simplex.closest_point().length();
+        if distance < TOUCHING_DISTANCE {
+            0.
+        } else {
+            distance
+        }
+    };
+
+    ClosestPoints {
+        distance,
+        point_a,
+        point_b,
+        simplex,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{circle::Circle, polygon::Polygon};
+
+    /// A thin wall whose left face is at x = 4.975.
+    fn wall() -> Polygon {
+        Polygon::rectangle(Pos2::at(5., 0.), 0.05, 6.)
+    }
+
+    /// Gaps from 0.001 to 0.4 at heights along the wall.
+    fn gaps() -> impl Iterator<Item = (f32, f32)> {
+        (0..20).flat_map(|i| {
+            (0..20).map(move |j| (0.001 + 0.399 * i as f32 / 19., -2. + 4. * j as f32 / 19.))
+        })
+    }
+
+    #[test]
+    fn circle_near_polygon_is_separated() {
+        for (gap, y) in gaps() {
+            let circle = Circle::new(Pos2::at(4.975 - 0.5 - gap, y), 0.5);
+
+            for closest in [
+                closest_points(&circle, &wall()),
+                closest_points(&wall(), &circle),
+            ] {
+                assert!(!closest.overlapping(), "gap {gap} at {y}: {closest:?}");
+                assert!(
+                    (closest.distance - gap).abs() < 1e-3,
+                    "gap {gap} at {y}: {closest:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn box_near_polygon_is_separated() {
+        for (gap, y) in gaps() {
+            let square = Polygon::rectangle(Pos2::at(4.975 - 0.5 - gap, y), 1., 1.);
+
+            let closest = closest_points(&square, &wall());
+            assert!(
+                (closest.distance - gap).abs() < 1e-4,
+                "gap {gap} at {y}: {closest:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn circle_overlapping_polygon() {
+        let circle = Circle::new(Pos2::at(4.975 - 0.4, 1.), 0.5);
+
+        assert!(closest_points(&circle, &wall()).overlapping());
+        assert!(closest_points(&wall(), &circle).overlapping());
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{
+    math::collision_manifold::{CollisionManifold, ContactId, ContactPoint},
+    shapes::Shape,
+};
+
+pub mod distance;
+pub mod epa;
+pub mod gjk;
+pub mod shape_cast;
+pub mod time_of_impact;
+
+/// Collides any two convex shapes through their support points, for pairs without a specialised test.
+///
+/// Only ever finds a single contact, at the deepest point of `b` inside `a`.
+pub fn collide(a: &impl Shape, b: &impl Shape) -> Option<CollisionManifold> {
+    let closest = gjk::closest_points(a, b);
+    if !closest.overlapping() {
+        return None;
+    }
+
+    let penetration = epa::penetration(a, b, &closest.simplex)?;
+
+    Some(CollisionManifold::new(
+        penetration.normal,
+        &[ContactPoint::new(
+            penetration.point_b,
+            penetration.depth,
+            ContactId::default(),
+        )],
+    ))
+}
@@ -0,0 +1,141 @@
+use crate::{
+    math::vec2::{Pos2, Vec2},
+    shapes::Shape,
+};
+
+use super::{epa, gjk};
+
+const MAX_ITERATIONS: usize = 32;
+// Gap at which the shapes are considered to be touching.
+const TOLERANCE: f32 = 1e-3;
+
+/// Where a swept shape first touches another.
+#[derive(Debug, Clone, Copy)]
+pub struct ShapeCastHit {
+    /// How far along the translation the shapes touch, from 0 to 1.
+    pub fraction: f32,
+    /// Point on the shape that was hit.
+    pub point: Pos2,
+    /// Normal of the shape that was hit at `point`, pointing back towards the swept shape.
+    pub normal: Vec2,
+}
+
+/// Sweeps `a` along `translation` and finds when it first touches `b`, with conservative advancement.
+///
+/// The gap between the closest points can only close as fast as `a` moves along the direction between them,
+/// so `a` can be moved that far without passing through `b`, repeating until the gap is gone. Shapes that
+/// already overlap are hit at 0.
+pub fn shape_cast<A: Shape + Clone>(
+    a: &A,
+    translation: Vec2,
+    b: &impl Shape,
+) -> Option<ShapeCastHit> {
+    let mut swept = a.clone();
+    let mut fraction = 0.;
+    let mut normal = -translation.normalized();
+
+    for _ in 0..MAX_ITERATIONS {
+        let closest = gjk::closest_points(&swept, b);
+
+        if closest.overlapping() {
+            if fraction == 0. {
+                // Overlapping from the start, push out the quickest way instead. Shapes that only touch have
+                // no such way, so they're hit head on.
+                return Some(match epa::penetration(&swept, b, &closest.simplex) {
+                    Some(penetration) => ShapeCastHit {
+                        fraction,
+                        point: penetration.point_b,
+                        normal: -penetration.normal,
+                    },
+                    None => ShapeCastHit {
+                        fraction,
+                        point: closest.point_b,
+                        normal,
+                    },
+                });
+            }
+
+            return Some(ShapeCastHit {
+                fraction,
+                point: closest.point_b,
+                normal,
+            });
+        }
+
+        normal = (closest.point_a - closest.point_b) / closest.distance;
+        if closest.distance < TOLERANCE {
+            return Some(ShapeCastHit {
+                fraction,
+                point: closest.point_b,
+                normal,
+            });
+        }
+
+        let approach = -translation.dot(normal);
+        if approach <= 0. {
+            // Moving apart, or sliding past.
+            return None;
+        }
+
+        // Aim for just inside the tolerance rather than touching, so this ends on a gap and not an overlap.
+        fraction += (closest.distance - 0.5 * TOLERANCE) / approach;
+        if fraction > 1. {
+            return None;
+        }
+
+        swept.displace(a.center() + translation * fraction - swept.center());
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{circle::Circle, polygon::Polygon};
+
+    /// A thin wall whose left face is at x = 4.975.
+    fn wall() -> Polygon {
+        Polygon::rectangle(Pos2::at(5., 0.), 0.05, 6.)
+    }
+
+    #[test]
+    fn cast_starting_near_target() {
+        let circle = Circle::new(Pos2::at(4.975 - 0.5 - 0.05, 0.3), 0.5);
+
+        let hit = shape_cast(&circle, Vec2::with(1., 0.), &wall()).unwrap();
+        assert!((hit.fraction - 0.05).abs() < 2e-3, "{hit:?}");
+        assert!((hit.normal.x + 1.).abs() < 1e-3, "{hit:?}");
+        assert!((hit.point.x - 4.975).abs() < 2e-3, "{hit:?}");
+    }
+
+    #[test]
+    fn cast_starting_far_from_target() {
+        let circle = Circle::new(Pos2::at(0., 1.), 0.5);
+
+        let hit = shape_cast(&circle, Vec2::with(8., 0.), &wall()).unwrap();
+        assert!((hit.fraction - 4.475 / 8.).abs() < 1e-3, "{hit:?}");
+        assert!((hit.normal.x + 1.).abs() < 1e-3, "{hit:?}");
+
+        assert!(shape_cast(&circle, Vec2::with(4., 0.), &wall()).is_none());
+        assert!(shape_cast(&circle, Vec2::with(-8., 0.), &wall()).is_none());
+    }
+
+    #[test]
+    fn cast_starting_touching_target() {
+        let circle = Circle::new(Pos2::at(4.975 - 0.5, 0.), 0.5);
+
+        let hit = shape_cast(&circle, Vec2::with(1., 0.), &wall()).unwrap();
+        assert_eq!(hit.fraction, 0.);
+        assert!(hit.normal.x.is_finite() && hit.normal.x < 0., "{hit:?}");
+    }
+
+    #[test]
+    fn cast_starting_on_a_point() {
+        let point = Circle::new(Pos2::at(1., 2.), 0.);
+
+        let hit = shape_cast(&point, Vec2::with(0., 3.), &point.clone()).unwrap();
+        assert_eq!(hit.fraction, 0.);
+        assert!((hit.normal.y + 1.).abs() < 1e-6, "{hit:?}");
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use crate::math::collision_manifold::CollisionManifold;
+
+/// Indices of the two bodies in `Simulation::items`, lowest index first.
+pub type BodyPair = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactState {
+    /// The bodies started touching this step.
+    Begin,
+    /// The bodies were already touching on the previous step.
+    Persist,
+    /// The bodies stopped touching this step. Only reported as an event, the manifold is dropped.
+    End,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    pub manifold: CollisionManifold,
+    pub state: ContactState,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ContactEvent {
+    pub pair: BodyPair,
+    pub state: ContactState,
+}
+
+/// Manifolds of every touching pair, carried from one step to the next so contacts can be matched by their
+/// `ContactId` to inherit the solver's impulses.
+#[derive(Default)]
+pub struct ContactCache {
+    contacts: BTreeMap<BodyPair, Contact>,
+    events: Vec<ContactEvent>,
+}
+
+impl ContactCache {
+    pub fn new() -> ContactCache {
+        ContactCache::default()
+    }
+
+    /// Replaces the cached manifolds with this step's, in ascending pair order, and records what changed.
+    pub fn update(&mut self, manifolds: impl IntoIterator<Item = (BodyPair, CollisionManifold)>) {
+        let mut previous = std::mem::take(&mut self.contacts);
+        self.events.clear();
+
+        for (pair, mut manifold) in manifolds {
+            let state = match previous.remove(&pair) {
+                Some(old) => {
+                    match_impulses(&mut manifold, &old.manifold);
+                    ContactState::Persist
+                }
+                None => ContactState::Begin,
+            };
+
+            self.events.push(ContactEvent { pair, state });
+            self.contacts.insert(pair, Contact { manifold, state });
+        }
+
+        for pair in previous.into_keys() {
+            self.events.push(ContactEvent {
+                pair,
+                state: ContactState::End,
+            });
+        }
+    }
+
+    pub fn get(&self, pair: BodyPair) -> Option<&Contact> {
+        self.contacts.get(&pair)
+    }
+
+    pub fn get_mut(&mut self, pair: BodyPair) -> Option<&mut Contact> {
+        self.contacts.get_mut(&pair)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BodyPair, &Contact)> {
+        self.contacts.iter().map(|(&pair, contact)| (pair, contact))
+    }
+
+    pub fn len(&self) -> usize {
+        self.contacts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contacts.is_empty()
+    }
+
+    /// What began, persisted and ended during the last `update`.
+    pub fn events(&self) -> &[ContactEvent] {
+        &self.events
+    }
+}
+
+/// Copies impulses from `previous` onto the contacts of `manifold` that were made by the same features.
+fn match_impulses(manifold: &mut CollisionManifold, previous: &CollisionManifold) {
+    for contact in manifold.contacts_mut() {
+        if let Some(old) = previous.contacts().iter().find(|old| old.id == contact.id) {
+            contact.normal_impulse = old.normal_impulse;
+            contact.tangent_impulse = old.tangent_impulse;
+        }
+    }
+}
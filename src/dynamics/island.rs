@@ -0,0 +1,69 @@
+use super::{contact_cache::ContactCache, rigid_body::RigidBody};
+
+/// Groups of dynamic bodies that touch each other, directly or through other dynamic bodies, by index in
+/// `Simulation::items`. Static and kinematic bodies don't join islands, so a whole level resting on the same
+/// ground still splits into one island per pile.
+pub fn find_islands(bodies: &[RigidBody], contacts: &ContactCache) -> Vec<Vec<usize>> {
+    let mut sets = DisjointSets::new(bodies.len());
+    for ((i, j), _) in contacts.iter() {
+        if bodies[i].is_dynamic() && bodies[j].is_dynamic() {
+            sets.union(i, j);
+        }
+    }
+
+    // Island of each root, in the order their first body appears.
+    let mut island_of = vec![usize::MAX; bodies.len()];
+    let mut islands: Vec<Vec<usize>> = Vec::new();
+    for (i, body) in bodies.iter().enumerate() {
+        if !body.is_dynamic() {
+            continue;
+        }
+
+        let root = sets.find(i);
+        if island_of[root] == usize::MAX {
+            island_of[root] = islands.len();
+            islands.push(Vec::new());
+        }
+        islands[island_of[root]].push(i);
+    }
+
+    islands
+}
+
+/// Union-find with path halving and union by size.
+struct DisjointSets {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(count: usize) -> Self {
+        Self {
+            parents: (0..count).collect(),
+            sizes: vec![1; count],
+        }
+    }
+
+    fn find(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+        index
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+
+        let (small, large) = if self.sizes[a] < self.sizes[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[small] = large;
+        self.sizes[large] += self.sizes[small];
+    }
+}
@@ -0,0 +1,4 @@
+pub mod contact_cache;
+pub mod island;
+pub mod rigid_body;
+pub mod solver;
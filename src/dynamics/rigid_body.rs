@@ -0,0 +1,303 @@
+use crate::{
+    collision::time_of_impact::Sweep,
+    math::{
+        isometry::Isometry,
+        mass_properties::MassProperties,
+        vec2::{Pos2, Vec2},
+    },
+    shapes::{Shape, ShapeVariant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyType {
+    /// Never moves and has infinite mass, like the ground.
+    Static,
+    /// Moves only by the velocity it is given and pushes dynamic bodies without being pushed back.
+    Kinematic,
+    /// Moved by gravity, forces and contacts.
+    Dynamic,
+}
+
+#[derive(Clone, Debug)]
+pub struct RigidBody {
+    pub shape: ShapeVariant,
+    body_type: BodyType,
+    pub velocity: Vec2,
+    pub angular_velocity: f32,
+    pub mass: f32,
+    pub inv_mass: f32,
+    pub inertia: f32,
+    pub inv_inertia: f32,
+    /// Offset from the shape's center to the center of mass, in world space. Kept in sync by `rotate`.
+    pub center_offset: Vec2,
+    pub restitution: f32,
+    pub friction: f32,
+    /// Multiplies `Simulation::gravity` for this body, 0 turns gravity off.
+    pub gravity_scale: f32,
+    /// Swept against everything it could pass through in a step, for small fast bodies like projectiles.
+    pub bullet: bool,
+    awake: bool,
+    /// How long the body has been slow enough to fall asleep, see `Simulation::time_to_sleep`.
+    pub sleep_time: f32,
+    // Accumulated over a step and cleared once it has been integrated.
+    pub force: Vec2,
+    pub torque: f32,
+    /// Pushes on the center of mass on every step until changed, unlike `force` which only lasts one step.
+    /// Meant for input that is held down, see `set_control`.
+    pub control_force: Vec2,
+    pub control_torque: f32,
+    /// Where the shape was before the last step, for drawing in between steps.
+    pub previous: Isometry,
+}
+
+impl RigidBody {
+    pub fn new(shape: ShapeVariant, mass: f32, inertia: f32) -> RigidBody {
+        let previous = shape.isometry();
+        let mut body = RigidBody {
+            shape,
+            body_type: BodyType::Dynamic,
+            velocity: Vec2::ZERO,
+            angular_velocity: 0.,
+            mass: 0.,
+            inv_mass: 0.,
+            inertia: 0.,
+            inv_inertia: 0.,
+            center_offset: Vec2::ZERO,
+            restitution: 0.2,
+            friction: 0.4,
+            gravity_scale: 1.,
+            bullet: false,
+            awake: true,
+            sleep_time: 0.,
+            force: Vec2::ZERO,
+            torque: 0.,
+            control_force: Vec2::ZERO,
+            control_torque: 0.,
+            previous,
+        };
+        body.set_mass(mass);
+        body.set_inertia(inertia);
+        body
+    }
+
+    /// Creates a body whose mass, center of mass and inertia come from filling `shape` with `density`.
+    pub fn from_shape(shape: ShapeVariant, density: f32) -> RigidBody {
+        let properties = shape.mass_properties(density);
+
+        let mut body = RigidBody::new(shape, 0., 0.);
+        body.set_mass_properties(properties);
+        body
+    }
+
+    /// Creates a body that never moves.
+    pub fn new_static(shape: ShapeVariant) -> RigidBody {
+        let mut body = RigidBody::new(shape, 0., 0.);
+        body.set_body_type(BodyType::Static);
+        body
+    }
+
+    pub fn body_type(&self) -> BodyType {
+        self.body_type
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.body_type == BodyType::Dynamic
+    }
+
+    /// Only dynamic bodies use their mass, the others act as if it were infinite. Static bodies also lose
+    /// their velocity.
+    pub fn set_body_type(&mut self, body_type: BodyType) {
+        self.body_type = body_type;
+        self.wake_up();
+
+        if body_type == BodyType::Static {
+            self.velocity = Vec2::ZERO;
+            self.angular_velocity = 0.;
+        }
+
+        self.set_mass(self.mass);
+        self.set_inertia(self.inertia);
+    }
+
+    pub fn set_mass_properties(&mut self, properties: MassProperties) {
+        self.set_mass(properties.mass);
+        self.set_inertia(properties.inertia);
+        self.set_center_of_mass(properties.center);
+    }
+
+    /// A mass of zero (or less) makes the body immovable: gravity, forces and contacts leave its velocity as
+    /// it is, like a kinematic body's.
+    pub fn set_mass(&mut self, mass: f32) {
+        self.mass = mass;
+        self.inv_mass = if mass > 0. && self.is_dynamic() {
+            mass.recip()
+        } else {
+            0.
+        };
+    }
+
+    pub fn set_inertia(&mut self, inertia: f32) {
+        self.inertia = inertia;
+        self.inv_inertia = if inertia > 0. && self.is_dynamic() {
+            inertia.recip()
+        } else {
+            0.
+        };
+    }
+
+    /// Moves the center of mass without moving the shape. The inertia is left as is, so set it afterwards if
+    /// it should be about the new center.
+    pub fn set_center_of_mass(&mut self, center: Pos2) {
+        self.center_offset = center - self.shape.center();
+    }
+
+    pub fn center_of_mass(&self) -> Pos2 {
+        self.shape.center() + self.center_offset
+    }
+
+    /// Rotates the body about its center of mass.
+    pub fn rotate(&mut self, angle: f32) {
+        let offset = self.center_offset;
+        self.center_offset = offset.rotate(angle);
+
+        self.shape.rotate(angle);
+        self.shape.displace(offset - self.center_offset);
+    }
+
+    /// Moves the shape to `isometry`, taking the center of mass along.
+    pub fn set_pose(&mut self, isometry: Isometry) {
+        self.rotate(isometry.angle - self.shape.angle());
+        self.shape.displace(isometry.position - self.shape.center());
+    }
+
+    /// How the body moved from the pose `start` to where it is now, like `previous`.
+    pub fn sweep(&self, start: Isometry) -> Sweep {
+        let angle = self.shape.angle();
+        let start_offset = self.center_offset.rotate(start.angle - angle);
+
+        Sweep {
+            center_start: start.position + start_offset,
+            center_end: self.center_of_mass(),
+            angle_start: start.angle,
+            angle_end: angle,
+            shape_offset: -start_offset,
+        }
+    }
+
+    /// Sleeping bodies are left out of integration and the narrowphase until something wakes them. Only
+    /// dynamic bodies fall asleep.
+    pub fn is_sleeping(&self) -> bool {
+        !self.awake
+    }
+
+    /// Call after moving a sleeping body or changing its velocity directly, forces and impulses wake it on
+    /// their own. The rest of its island wakes on the next step.
+    pub fn wake_up(&mut self) {
+        self.awake = true;
+        self.sleep_time = 0.;
+    }
+
+    /// Stops the body where it is until something wakes it.
+    pub fn sleep(&mut self) {
+        if !self.is_dynamic() {
+            return;
+        }
+
+        self.awake = false;
+        self.sleep_time = 0.;
+        self.velocity = Vec2::ZERO;
+        self.angular_velocity = 0.;
+        self.force = Vec2::ZERO;
+        self.torque = 0.;
+    }
+
+    /// Velocity of the body at a world space point, including the contribution of its spin.
+    pub fn velocity_at(&self, point: Pos2) -> Vec2 {
+        self.velocity + (point - self.center_of_mass()).perpendicular() * self.angular_velocity
+    }
+
+    /// Applies `impulse` at `contact_vector`, an offset from the center of mass.
+    pub fn apply_impulse(&mut self, impulse: Vec2, contact_vector: Vec2) {
+        self.velocity += impulse * self.inv_mass;
+        self.angular_velocity += contact_vector.cross(impulse) * self.inv_inertia;
+    }
+
+    /// Pushes on the center of mass until the end of the next step.
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.wake_up();
+        self.force += force;
+    }
+
+    /// Pushes on a world space point until the end of the next step, which also spins the body if the point
+    /// is off its center of mass.
+    pub fn apply_force_at_point(&mut self, force: Vec2, point: Pos2) {
+        self.wake_up();
+        self.force += force;
+        self.torque += (point - self.center_of_mass()).cross(force);
+    }
+
+    /// Keeps pushing and spinning the body on every step until called again, zero stops it. A body under
+    /// control doesn't fall asleep.
+    pub fn set_control(&mut self, force: Vec2, torque: f32) {
+        self.wake_up();
+        self.control_force = force;
+        self.control_torque = torque;
+    }
+
+    pub fn is_controlled(&self) -> bool {
+        self.control_force.length_squared() > 0. || self.control_torque != 0.
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.wake_up();
+        self.torque += torque;
+    }
+
+    /// Immediately changes the velocity as if `impulse` hit the body at a world space point.
+    pub fn apply_linear_impulse(&mut self, impulse: Vec2, point: Pos2) {
+        self.wake_up();
+        self.apply_impulse(impulse, point - self.center_of_mass());
+    }
+
+    pub fn apply_angular_impulse(&mut self, impulse: f32) {
+        self.wake_up();
+        self.angular_velocity += impulse * self.inv_inertia;
+    }
+
+    /// A copy of the shape placed between its previous and current pose, `alpha` going from 0 to 1.
+    pub fn interpolated_shape(&self, alpha: f32) -> ShapeVariant {
+        let mut shape = self.shape.clone();
+        shape.set_isometry(self.previous.lerp(self.shape.isometry(), alpha));
+        shape
+    }
+
+    /// First half of semi-implicit Euler: gravity and the accumulated forces change the velocity of dynamic
+    /// bodies. The forces stay until `clear_forces`, so a step split into substeps can integrate them in each.
+    pub fn integrate_velocity(&mut self, dt: f32, gravity: Vec2) {
+        if !self.is_dynamic() || !self.awake {
+            return;
+        }
+
+        // Without mass nothing could stop the body falling, not even the ground.
+        if self.inv_mass > 0. {
+            self.velocity += gravity * (self.gravity_scale * dt);
+            self.velocity += (self.force + self.control_force) * (self.inv_mass * dt);
+        }
+        self.angular_velocity += (self.torque + self.control_torque) * self.inv_inertia * dt;
+    }
+
+    pub fn clear_forces(&mut self) {
+        self.force = Vec2::ZERO;
+        self.torque = 0.;
+    }
+
+    /// Second half of semi-implicit Euler: the new velocity moves the shape.
+    pub fn integrate_position(&mut self, dt: f32) {
+        if self.body_type == BodyType::Static || !self.awake {
+            return;
+        }
+
+        self.shape.displace(self.velocity * dt);
+        self.rotate(self.angular_velocity * dt);
+    }
+}
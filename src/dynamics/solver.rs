@@ -0,0 +1,478 @@
+use crate::{
+    math::vec2::{Pos2, Vec2},
+    shapes::Shape,
+};
+
+use super::{
+    contact_cache::{BodyPair, ContactCache},
+    rigid_body::RigidBody,
+};
+
+// Approach speed below which contacts stop bouncing, so resting bodies don't jitter.
+const RESTITUTION_THRESHOLD: f32 = 1.;
+// Overlap that is allowed to remain, which keeps contacts alive between steps.
+const LINEAR_SLOP: f32 = 0.005;
+const BAUMGARTE: f32 = 0.2;
+const MAX_LINEAR_CORRECTION: f32 = 0.2;
+// Fastest soft contacts push overlapping bodies apart, so deep overlaps resolve without an explosion.
+const MAX_PUSH_VELOCITY: f32 = 3.;
+
+/// How contacts are resolved each `Simulation` step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolverKind {
+    /// Solves velocities `Simulation::velocity_iterations` times over the whole step, then corrects what
+    /// overlap is left by moving the bodies directly.
+    Iterations,
+    /// Splits the step into `substeps`, each integrating the bodies again and relaxing the contacts. Contacts
+    /// push apart like a spring of `contact_hertz` with `damping_ratio`, which holds up better in tall stacks
+    /// and under heavy bodies than correcting positions.
+    ///
+    /// `contact_hertz` is capped at a quarter of the substep rate, so stiffer contacts also take more
+    /// substeps. How stiff they are decides how heavy a body the one under it can hold: a contact gives way by
+    /// about the weight on it over `mass * (2π * contact_hertz)²` of the lighter body. At 60 steps a second the
+    /// defaults hold a box 300 times heavier than the one it rests on, 8 substeps at 120 Hz hold 1000 times.
+    SoftStep {
+        substeps: usize,
+        contact_hertz: f32,
+        damping_ratio: f32,
+    },
+}
+
+impl SolverKind {
+    /// `SoftStep` with settings that suit most scenes, as stiff as 4 substeps of a 60 Hz step allow.
+    pub fn soft_step() -> SolverKind {
+        SolverKind::SoftStep {
+            substeps: 4,
+            contact_hertz: 60.,
+            damping_ratio: 10.,
+        }
+    }
+}
+
+/// Coefficients of a soft constraint for one substep, see "Solver2D" by Erin Catto.
+#[derive(Debug, Clone, Copy)]
+pub struct Softness {
+    bias_rate: f32,
+    mass_scale: f32,
+    impulse_scale: f32,
+}
+
+impl Softness {
+    pub fn new(hertz: f32, damping_ratio: f32, h: f32) -> Softness {
+        let omega = 2. * std::f32::consts::PI * hertz;
+        let a1 = 2. * damping_ratio + h * omega;
+        let a2 = h * omega * a1;
+        let a3 = 1. / (1. + a2);
+
+        Softness {
+            bias_rate: omega / a1,
+            mass_scale: a2 * a3,
+            impulse_scale: a3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ConstraintPoint {
+    anchor_a: Vec2,
+    anchor_b: Vec2,
+    // Separation at the start of the step, minus what the anchors account for.
+    adjusted_separation: f32,
+    normal_mass: f32,
+    tangent_mass: f32,
+    velocity_bias: f32,
+    // Normal velocity before solving, what restitution bounces back.
+    relative_velocity: f32,
+    normal_impulse: f32,
+    tangent_impulse: f32,
+    max_normal_impulse: f32,
+}
+
+#[derive(Debug, Clone)]
+struct ContactConstraint {
+    pair: BodyPair,
+    normal: Vec2,
+    tangent: Vec2,
+    friction: f32,
+    restitution: f32,
+    points: [ConstraintPoint; 2],
+    point_count: usize,
+}
+
+/// Sequential impulse solver in the style of Box2D.
+///
+/// Normal and friction impulses are accumulated and clamped over several velocity iterations, starting from the
+/// impulses of the previous step. Remaining overlap is then removed by moving the bodies directly.
+pub struct ContactSolver {
+    constraints: Vec<ContactConstraint>,
+    // Center of mass and angle of every body when the constraints were built.
+    origins: Vec<(Pos2, f32)>,
+}
+
+impl ContactSolver {
+    /// Contacts with a negative depth are speculative, the bodies may close the gap over the step of length `dt`
+    /// but not go past it.
+    pub fn new(bodies: &[RigidBody], contacts: &ContactCache, dt: f32) -> ContactSolver {
+        let mut constraints = Vec::with_capacity(contacts.len());
+
+        for ((i, j), contact) in contacts.iter() {
+            let manifold = &contact.manifold;
+            let (a, b) = (&bodies[i], &bodies[j]);
+            // A sleeping body only touches other sleeping or immovable ones, nothing there needs solving.
+            if a.inv_mass + b.inv_mass == 0. || a.is_sleeping() || b.is_sleeping() {
+                continue;
+            }
+
+            let normal = manifold.normal;
+            let tangent = normal.orthogonal();
+            let restitution = a.restitution.max(b.restitution);
+            let center_a = a.center_of_mass();
+            let center_b = b.center_of_mass();
+
+            let mut points = [ConstraintPoint::default(); 2];
+            for (point, contact) in points.iter_mut().zip(manifold.contacts()) {
+                let anchor_a = contact.point - center_a;
+                let anchor_b = contact.point - center_b;
+
+                let relative_velocity = b.velocity_at(contact.point) - a.velocity_at(contact.point);
+                let normal_velocity = relative_velocity.dot(normal);
+
+                *point = ConstraintPoint {
+                    anchor_a,
+                    anchor_b,
+                    adjusted_separation: -contact.depth - (anchor_b - anchor_a).dot(normal),
+                    normal_mass: effective_mass(a, b, anchor_a, anchor_b, normal),
+                    tangent_mass: effective_mass(a, b, anchor_a, anchor_b, tangent),
+                    velocity_bias: if contact.depth < 0. {
+                        contact.depth / dt
+                    } else if normal_velocity < -RESTITUTION_THRESHOLD {
+                        -restitution * normal_velocity
+                    } else {
+                        0.
+                    },
+                    relative_velocity: normal_velocity,
+                    normal_impulse: contact.normal_impulse,
+                    tangent_impulse: contact.tangent_impulse,
+                    max_normal_impulse: 0.,
+                };
+            }
+
+            constraints.push(ContactConstraint {
+                pair: (i, j),
+                normal,
+                tangent,
+                friction: (a.friction * b.friction).sqrt(),
+                restitution,
+                points,
+                point_count: manifold.contact_count,
+            });
+        }
+
+        ContactSolver {
+            constraints,
+            origins: bodies
+                .iter()
+                .map(|body| (body.center_of_mass(), body.shape.angle()))
+                .collect(),
+        }
+    }
+
+    /// Applies the impulses carried over from the previous step.
+    pub fn warm_start(&self, bodies: &mut [RigidBody]) {
+        for constraint in &self.constraints {
+            let (a, b) = pair_mut(bodies, constraint.pair.0, constraint.pair.1);
+
+            for point in &constraint.points[..constraint.point_count] {
+                let impulse = constraint.normal * point.normal_impulse
+                    + constraint.tangent * point.tangent_impulse;
+
+                a.apply_impulse(-impulse, point.anchor_a);
+                b.apply_impulse(impulse, point.anchor_b);
+            }
+        }
+    }
+
+    pub fn solve_velocities(&mut self, bodies: &mut [RigidBody]) {
+        for constraint in &mut self.constraints {
+            let (a, b) = pair_mut(bodies, constraint.pair.0, constraint.pair.1);
+            let normal = constraint.normal;
+            let tangent = constraint.tangent;
+
+            for point in &mut constraint.points[..constraint.point_count] {
+                // Friction first, so it is bounded by a normal impulse from the last iteration.
+                let relative_velocity = contact_velocity(a, b, point);
+                let lambda = -point.tangent_mass * relative_velocity.dot(tangent);
+                let max_friction = constraint.friction * point.normal_impulse;
+                let new_impulse =
+                    (point.tangent_impulse + lambda).clamp(-max_friction, max_friction);
+                let lambda = new_impulse - point.tangent_impulse;
+                point.tangent_impulse = new_impulse;
+
+                a.apply_impulse(-tangent * lambda, point.anchor_a);
+                b.apply_impulse(tangent * lambda, point.anchor_b);
+
+                let relative_velocity = contact_velocity(a, b, point);
+                let lambda =
+                    -point.normal_mass * (relative_velocity.dot(normal) - point.velocity_bias);
+                let new_impulse = (point.normal_impulse + lambda).max(0.);
+                let lambda = new_impulse - point.normal_impulse;
+                point.normal_impulse = new_impulse;
+
+                a.apply_impulse(-normal * lambda, point.anchor_a);
+                b.apply_impulse(normal * lambda, point.anchor_b);
+            }
+        }
+    }
+
+    /// One pass of the soft step solver over a substep of length `h`. With `use_bias` overlap is pushed out
+    /// through `softness`, without it the contacts only stop the bodies approaching, which relaxes away the
+    /// velocity the push left behind.
+    pub fn solve_soft(
+        &mut self,
+        bodies: &mut [RigidBody],
+        h: f32,
+        softness: &Softness,
+        use_bias: bool,
+    ) {
+        for constraint in &mut self.constraints {
+            let (i, j) = constraint.pair;
+            let normal = constraint.normal;
+            let tangent = constraint.tangent;
+
+            for point in &mut constraint.points[..constraint.point_count] {
+                let (a, b) = pair_mut(bodies, i, j);
+                let separation =
+                    current_separation(&self.origins, a, b, constraint.pair, normal, point).2;
+
+                let (bias, mass_scale, impulse_scale) = if separation > 0. {
+                    // Not touching yet, let the gap close over this substep.
+                    (separation / h, 1., 0.)
+                } else if use_bias {
+                    (
+                        (softness.bias_rate * separation).max(-MAX_PUSH_VELOCITY),
+                        softness.mass_scale,
+                        softness.impulse_scale,
+                    )
+                } else {
+                    (0., 1., 0.)
+                };
+
+                let relative_velocity = contact_velocity(a, b, point);
+                let lambda =
+                    -point.normal_mass * mass_scale * (relative_velocity.dot(normal) + bias)
+                        - impulse_scale * point.normal_impulse;
+                let new_impulse = (point.normal_impulse + lambda).max(0.);
+                let lambda = new_impulse - point.normal_impulse;
+                point.normal_impulse = new_impulse;
+                point.max_normal_impulse = point.max_normal_impulse.max(new_impulse);
+
+                a.apply_impulse(-normal * lambda, point.anchor_a);
+                b.apply_impulse(normal * lambda, point.anchor_b);
+
+                let relative_velocity = contact_velocity(a, b, point);
+                let lambda = -point.tangent_mass * relative_velocity.dot(tangent);
+                let max_friction = constraint.friction * point.normal_impulse;
+                let new_impulse =
+                    (point.tangent_impulse + lambda).clamp(-max_friction, max_friction);
+                let lambda = new_impulse - point.tangent_impulse;
+                point.tangent_impulse = new_impulse;
+
+                a.apply_impulse(-tangent * lambda, point.anchor_a);
+                b.apply_impulse(tangent * lambda, point.anchor_b);
+            }
+        }
+    }
+
+    /// Bounces contacts that hit fast enough once the soft step solver is done, since the soft contacts
+    /// themselves would only absorb the hit.
+    pub fn apply_restitution(&mut self, bodies: &mut [RigidBody]) {
+        for constraint in &mut self.constraints {
+            if constraint.restitution == 0. {
+                continue;
+            }
+
+            let (a, b) = pair_mut(bodies, constraint.pair.0, constraint.pair.1);
+            let normal = constraint.normal;
+
+            for point in &mut constraint.points[..constraint.point_count] {
+                if point.relative_velocity > -RESTITUTION_THRESHOLD
+                    || point.max_normal_impulse == 0.
+                {
+                    continue;
+                }
+
+                let relative_velocity = contact_velocity(a, b, point);
+                let lambda = -point.normal_mass
+                    * (relative_velocity.dot(normal)
+                        + constraint.restitution * point.relative_velocity);
+                let new_impulse = (point.normal_impulse + lambda).max(0.);
+                let lambda = new_impulse - point.normal_impulse;
+                point.normal_impulse = new_impulse;
+
+                a.apply_impulse(-normal * lambda, point.anchor_a);
+                b.apply_impulse(normal * lambda, point.anchor_b);
+            }
+        }
+    }
+
+    /// Pushes overlapping bodies apart, returns true once every contact is within the allowed slop.
+    pub fn solve_positions(&self, bodies: &mut [RigidBody]) -> bool {
+        let mut min_separation: f32 = 0.;
+
+        for constraint in &self.constraints {
+            let (i, j) = constraint.pair;
+            let normal = constraint.normal;
+
+            for point in &constraint.points[..constraint.point_count] {
+                let (a, b) = pair_mut(bodies, i, j);
+                let (anchor_a, anchor_b, separation) =
+                    current_separation(&self.origins, a, b, constraint.pair, normal, point);
+                min_separation = min_separation.min(separation);
+
+                let correction =
+                    (BAUMGARTE * (separation + LINEAR_SLOP)).clamp(-MAX_LINEAR_CORRECTION, 0.);
+                let mass = effective_mass(a, b, anchor_a, anchor_b, normal);
+                let impulse = normal * (-correction * mass);
+
+                a.shape.displace(-impulse * a.inv_mass);
+                a.rotate(-anchor_a.cross(impulse) * a.inv_inertia);
+                b.shape.displace(impulse * b.inv_mass);
+                b.rotate(anchor_b.cross(impulse) * b.inv_inertia);
+            }
+        }
+
+        min_separation >= -3. * LINEAR_SLOP
+    }
+
+    /// Writes the accumulated impulses back so the next step can warm start from them.
+    pub fn store_impulses(&self, contacts: &mut ContactCache) {
+        for constraint in &self.constraints {
+            let Some(cached) = contacts.get_mut(constraint.pair) else {
+                continue;
+            };
+
+            for (contact, point) in cached
+                .manifold
+                .contacts_mut()
+                .iter_mut()
+                .zip(&constraint.points)
+            {
+                contact.normal_impulse = point.normal_impulse;
+                contact.tangent_impulse = point.tangent_impulse;
+            }
+        }
+    }
+}
+
+/// Borrows two distinct bodies at once, `i` must be less than `j`.
+pub fn pair_mut(items: &mut [RigidBody], i: usize, j: usize) -> (&mut RigidBody, &mut RigidBody) {
+    let (head, tail) = items.split_at_mut(j);
+    (&mut head[i], &mut tail[0])
+}
+
+/// Anchors of `point` turned with the bodies since the constraints were built, and how far apart the bodies
+/// are at it now.
+fn current_separation(
+    origins: &[(Pos2, f32)],
+    a: &RigidBody,
+    b: &RigidBody,
+    (i, j): BodyPair,
+    normal: Vec2,
+    point: &ConstraintPoint,
+) -> (Vec2, Vec2, f32) {
+    let (origin_a, angle_a) = origins[i];
+    let (origin_b, angle_b) = origins[j];
+
+    let anchor_a = point.anchor_a.rotate(a.shape.angle() - angle_a);
+    let anchor_b = point.anchor_b.rotate(b.shape.angle() - angle_b);
+    let delta = (b.center_of_mass() - origin_b) - (a.center_of_mass() - origin_a);
+    let separation = (delta + anchor_b - anchor_a).dot(normal) + point.adjusted_separation;
+
+    (anchor_a, anchor_b, separation)
+}
+
+fn contact_velocity(a: &RigidBody, b: &RigidBody, point: &ConstraintPoint) -> Vec2 {
+    b.velocity + point.anchor_b.perpendicular() * b.angular_velocity
+        - a.velocity
+        - point.anchor_a.perpendicular() * a.angular_velocity
+}
+
+fn effective_mass(
+    a: &RigidBody,
+    b: &RigidBody,
+    anchor_a: Vec2,
+    anchor_b: Vec2,
+    direction: Vec2,
+) -> f32 {
+    let r_a_cross = anchor_a.cross(direction);
+    let r_b_cross = anchor_b.cross(direction);
+    let k = a.inv_mass
+        + b.inv_mass
+        + r_a_cross * r_a_cross * a.inv_inertia
+        + r_b_cross * r_b_cross * b.inv_inertia;
+
+    if k > 0. {
+        k.recip()
+    } else {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::vec2::Pos2,
+        shapes::{polygon::Polygon, ShapeVariant},
+        simulation::Simulation,
+    };
+
+    /// A unit box `ratio` times as dense as the unit box it rests on, both on the ground.
+    fn stack(solver: SolverKind, ratio: f32) -> Simulation {
+        let mut sim = Simulation::new();
+        sim.solver = solver;
+        sim.sleeping_enabled = false;
+        sim.gravity = Vec2::with(0., 9.81);
+
+        let rectangle =
+            |y, size| ShapeVariant::Polygon(Polygon::rectangle(Pos2::at(0., y), size, size));
+        sim.items.push(RigidBody::new_static(rectangle(20., 2.)));
+        sim.items
+            .push(RigidBody::from_shape(rectangle(18.5, 1.), 1.));
+        sim.items
+            .push(RigidBody::from_shape(rectangle(17.5, 1.), ratio));
+
+        for _ in 0..600 {
+            sim.update(1. / 60.);
+        }
+        sim
+    }
+
+    fn assert_rests(sim: &Simulation, tolerance: f32) {
+        for (body, y) in sim.items[1..].iter().zip([18.5, 17.5]) {
+            let center = body.shape.center();
+            assert!(center.x.abs() < 0.05, "slid off to {center:?}");
+            assert!((center.y - y).abs() < tolerance, "sank to {center:?}");
+            assert!(
+                body.velocity.length() < 0.05,
+                "still moving at {:?}",
+                body.velocity
+            );
+        }
+    }
+
+    #[test]
+    fn soft_step_holds_300_to_1() {
+        assert_rests(&stack(SolverKind::soft_step(), 300.), 0.1);
+    }
+
+    #[test]
+    fn stiff_soft_step_holds_1000_to_1() {
+        let solver = SolverKind::SoftStep {
+            substeps: 8,
+            contact_hertz: 120.,
+            damping_ratio: 10.,
+        };
+        assert_rests(&stack(solver, 1000.), 0.1);
+    }
+}
@@ -0,0 +1,110 @@
+use macroquad::input::{is_key_down, is_key_pressed, KeyCode};
+use ram_phys::{
+    math::vec2::Vec2,
+    simulation::{Command, Simulation},
+};
+
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub rotate_left: KeyCode,
+    pub rotate_right: KeyCode,
+    pub toggle_solver: KeyCode,
+    pub next_body: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: KeyCode::W,
+            down: KeyCode::S,
+            left: KeyCode::A,
+            right: KeyCode::D,
+            rotate_left: KeyCode::Q,
+            rotate_right: KeyCode::E,
+            toggle_solver: KeyCode::Space,
+            next_body: KeyCode::Tab,
+        }
+    }
+}
+
+/// Turns key presses into commands for one body of the simulation.
+pub struct InputController {
+    pub bindings: KeyBindings,
+    /// Index of the controlled body in `Simulation::items`.
+    pub body: usize,
+    // Scaled by the body's mass and inertia, so every body handles the same.
+    pub acceleration: f32,
+    pub angular_acceleration: f32,
+    // Control last sent, so a command only goes out when the held keys change.
+    control: (Vec2, f32),
+}
+
+impl InputController {
+    pub fn new(body: usize) -> Self {
+        Self {
+            bindings: KeyBindings::default(),
+            body,
+            acceleration: 20.,
+            angular_acceleration: 5.,
+            control: (Vec2::ZERO, 0.),
+        }
+    }
+
+    pub fn update(&mut self, sim: &mut Simulation) {
+        if is_key_pressed(self.bindings.toggle_solver) {
+            sim.send(Command::ToggleSolver);
+        }
+        if is_key_pressed(self.bindings.next_body) && !sim.items.is_empty() {
+            self.set_control(sim, Vec2::ZERO, 0.);
+            self.body = (self.body + 1) % sim.items.len();
+        }
+
+        let Some(body) = sim.items.get(self.body) else {
+            return;
+        };
+
+        let mut direction = Vec2::ZERO;
+        if is_key_down(self.bindings.up) {
+            direction.y -= 1.;
+        }
+        if is_key_down(self.bindings.down) {
+            direction.y += 1.;
+        }
+        if is_key_down(self.bindings.left) {
+            direction.x -= 1.;
+        }
+        if is_key_down(self.bindings.right) {
+            direction.x += 1.;
+        }
+
+        let mut spin = 0.;
+        if is_key_down(self.bindings.rotate_left) {
+            spin -= 1.;
+        }
+        if is_key_down(self.bindings.rotate_right) {
+            spin += 1.;
+        }
+
+        // A control force rather than a force per frame, so it pushes the same however many steps a frame runs.
+        let force = direction * (self.acceleration * body.mass);
+        let torque = spin * self.angular_acceleration * body.inertia;
+        self.set_control(sim, force, torque);
+    }
+
+    fn set_control(&mut self, sim: &mut Simulation, force: Vec2, torque: f32) {
+        let (last_force, last_torque) = self.control;
+        if (force - last_force).length_squared() == 0. && torque == last_torque {
+            return;
+        }
+
+        self.control = (force, torque);
+        sim.send(Command::SetControl {
+            body: self.body,
+            force,
+            torque,
+        });
+    }
+}
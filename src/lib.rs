@@ -0,0 +1,8 @@
+pub mod broadphase;
+pub mod collision;
+pub mod dynamics;
+pub mod math;
+pub mod shapes;
+pub mod simulation;
+#[cfg(feature = "render")]
+pub mod util;
@@ -1,44 +1,53 @@
-pub mod math;
-pub mod shapes;
-pub mod simulation;
-pub mod util;
+mod input;
 
+use input::InputController;
 use macroquad::{
-    input::is_key_down,
     time::get_frame_time,
     window::{clear_background, next_frame},
 };
 // use macroquad::prelude::*;
-use math::vec2::{Pos2, Vec2};
-use shapes::{circle::Circle, polygon::Polygon, Collision, Shape, ShapeVariant};
-use simulation::Simulation;
-use util::{DrawTransform, Drawable, BG, RED};
+use ram_phys::{
+    dynamics::rigid_body::RigidBody,
+    math::vec2::{Pos2, Vec2},
+    shapes::{circle::Circle, polygon::Polygon, ShapeVariant},
+    simulation::Simulation,
+    util::{DrawTransform, Drawable, BG},
+};
 
 // Since I intend to separate the physics code from the renderer, I will use my own classes for Vectors, but colors can stay with the Macroquad API.
 
 #[macroquad::main("2d Physics")]
 async fn main() {
     let mut sim = Simulation::new();
+    sim.gravity = Vec2::with(0., 9.81);
 
-    sim.items
-        .push(ShapeVariant::Polygon(Polygon::from_points(vec![
+    sim.items.push(RigidBody::from_shape(
+        ShapeVariant::Polygon(Polygon::from_points(vec![
             Vec2::with(25., 25.),
             Vec2::with(30., 25.),
             Vec2::with(35., 30.),
             Vec2::with(25., 30.),
-        ])));
-    sim.items
-        .push(ShapeVariant::Polygon(Polygon::from_points(vec![
+        ])),
+        1.,
+    ));
+    sim.items.push(RigidBody::from_shape(
+        ShapeVariant::Polygon(Polygon::from_points(vec![
             Vec2::with(15., 15.),
             Vec2::with(20., 15.),
             Vec2::with(25., 20.),
             Vec2::with(15., 20.),
-        ])));
+        ])),
+        1.,
+    ));
+
+    sim.items.push(RigidBody::from_shape(
+        ShapeVariant::Circle(Circle::new(Pos2::at(10., 10.), 5.)),
+        1.,
+    ));
 
-    sim.items.push(ShapeVariant::Circle(Circle {
-        position: Pos2::at(10., 10.),
-        radius: 5.,
-    }));
+    sim.items.push(RigidBody::new_static(ShapeVariant::Polygon(
+        Polygon::rectangle(Pos2::at(16., 22.), 30., 1.),
+    )));
 
     let t = DrawTransform {
         x: 0.,
@@ -46,9 +55,12 @@ async fn main() {
         zoom: 25.,
     };
 
+    let mut controller = InputController::new(0);
+
     loop {
         clear_background(BG);
-        sim.update(get_frame_time());
+        controller.update(&mut sim);
+        sim.step(get_frame_time());
 
         sim.draw(&t);
 
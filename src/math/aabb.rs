@@ -0,0 +1,129 @@
+use super::{
+    ray::Ray,
+    vec2::{Pos2, Vec2},
+};
+
+/// Axis aligned bounding box.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Pos2,
+    pub max: Pos2,
+}
+
+impl Aabb {
+    pub fn new(min: Pos2, max: Pos2) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box around every point, or `None` if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Pos2>) -> Option<Self> {
+        points.into_iter().fold(None, |aabb: Option<Aabb>, p| {
+            Some(match aabb {
+                Some(aabb) => Aabb {
+                    min: Pos2::at(aabb.min.x.min(p.x), aabb.min.y.min(p.y)),
+                    max: Pos2::at(aabb.max.x.max(p.x), aabb.max.y.max(p.y)),
+                },
+                None => Aabb { min: p, max: p },
+            })
+        })
+    }
+
+    pub fn center(&self) -> Pos2 {
+        self.min.midpoint(self.max)
+    }
+
+    pub fn extents(&self) -> Vec2 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        2. * ((self.max.x - self.min.x) + (self.max.y - self.min.y))
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+    }
+
+    /// True if `other` lies entirely inside `self`.
+    pub fn contains(&self, other: &Aabb) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && self.max.x >= other.max.x
+            && self.max.y >= other.max.y
+    }
+
+    pub fn contains_point(&self, point: Pos2) -> bool {
+        self.min.x <= point.x
+            && self.min.y <= point.y
+            && self.max.x >= point.x
+            && self.max.y >= point.y
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Pos2::at(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Pos2::at(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    /// Grows the box by `margin` on every side.
+    pub fn fattened(&self, margin: f32) -> Aabb {
+        let margin = Vec2::with(margin, margin);
+
+        Aabb {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    /// Stretches the box in the direction of `displacement`, so it also covers where it is heading.
+    pub fn swept(&self, displacement: Vec2) -> Aabb {
+        let mut swept = *self;
+
+        if displacement.x < 0. {
+            swept.min.x += displacement.x;
+        } else {
+            swept.max.x += displacement.x;
+        }
+        if displacement.y < 0. {
+            swept.min.y += displacement.y;
+        } else {
+            swept.max.y += displacement.y;
+        }
+
+        swept
+    }
+
+    /// How far along `ray` it enters the box, 0 if it starts inside, or `None` if it misses before `max_t`.
+    pub fn raycast(&self, ray: &Ray, max_t: f32) -> Option<f32> {
+        let mut enter: f32 = 0.;
+        let mut exit = max_t;
+
+        for (origin, direction, min, max) in [
+            (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+            (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+        ] {
+            if direction == 0. {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let inv_direction = direction.recip();
+            let t1 = (min - origin) * inv_direction;
+            let t2 = (max - origin) * inv_direction;
+
+            enter = enter.max(t1.min(t2));
+            exit = exit.min(t1.max(t2));
+            if enter > exit {
+                return None;
+            }
+        }
+
+        Some(enter)
+    }
+}
@@ -1,22 +1,111 @@
 use std::ops::Neg;
 
-use super::{ray::Ray, vec2::Vec2};
+use super::{
+    ray::Ray,
+    vec2::{Pos2, Vec2},
+};
 
+/// Identifies the features of the two shapes that produced a contact, so it can be matched across steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ContactId {
+    pub reference_edge: u8,
+    pub incident_vertex: u8,
+    /// Set when the manifold was built with the shapes the other way around.
+    pub flipped: bool,
+}
+
+impl ContactId {
+    /// Stands in for a feature that doesn't exist, like the edge of a circle.
+    pub const NO_FEATURE: u8 = u8::MAX;
+
+    pub fn new(reference_edge: usize, incident_vertex: usize) -> Self {
+        Self {
+            reference_edge: reference_edge as u8,
+            incident_vertex: incident_vertex as u8,
+            flipped: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ContactPoint {
+    pub point: Pos2,
+    pub depth: f32,
+    pub id: ContactId,
+    // Accumulated by the solver and carried over to the next step for warm starting.
+    pub normal_impulse: f32,
+    pub tangent_impulse: f32,
+}
+
+impl ContactPoint {
+    pub fn new(point: Pos2, depth: f32, id: ContactId) -> Self {
+        Self {
+            point,
+            depth,
+            id,
+            normal_impulse: 0.,
+            tangent_impulse: 0.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct CollisionManifold {
     pub normal: Vec2,
-    // pub penetration_point: Pos2,
+    // Deepest contact, kept alongside `contacts` for drawing and single point consumers.
     pub penetration: Ray,
     pub depth: f32,
+    pub contacts: [ContactPoint; 2],
+    pub contact_count: usize,
+}
+
+impl CollisionManifold {
+    /// Builds a manifold from one or two contacts that share `normal`.
+    pub fn new(normal: Vec2, contacts: &[ContactPoint]) -> Self {
+        assert!(
+            (1..=2).contains(&contacts.len()),
+            "a manifold holds one or two contacts"
+        );
+
+        let deepest = contacts
+            .iter()
+            .copied()
+            .reduce(|a, b| if b.depth > a.depth { b } else { a })
+            .unwrap();
+
+        Self {
+            normal,
+            penetration: Ray {
+                origin: deepest.point,
+                direction: normal * deepest.depth,
+            },
+            depth: deepest.depth,
+            contacts: [contacts[0], *contacts.last().unwrap()],
+            contact_count: contacts.len(),
+        }
+    }
+
+    pub fn contacts(&self) -> &[ContactPoint] {
+        &self.contacts[..self.contact_count]
+    }
+
+    pub fn contacts_mut(&mut self) -> &mut [ContactPoint] {
+        &mut self.contacts[..self.contact_count]
+    }
 }
 
 impl Neg for CollisionManifold {
     type Output = Self;
 
-    fn neg(self) -> Self::Output {
+    fn neg(mut self) -> Self::Output {
+        for contact in self.contacts_mut() {
+            contact.id.flipped = !contact.id.flipped;
+        }
+
         Self {
             normal: -self.normal,
             penetration: -self.penetration,
-            depth: self.depth,
+            ..self
         }
     }
 }
@@ -0,0 +1,22 @@
+use super::vec2::Pos2;
+
+/// Position and rotation of a shape, without the shape itself.
+#[derive(Debug, Clone, Copy)]
+pub struct Isometry {
+    pub position: Pos2,
+    pub angle: f32,
+}
+
+impl Isometry {
+    pub fn new(position: Pos2, angle: f32) -> Self {
+        Self { position, angle }
+    }
+
+    /// Linear blend between `self` at `alpha = 0` and `other` at `alpha = 1`.
+    pub fn lerp(self, other: Isometry, alpha: f32) -> Isometry {
+        Isometry {
+            position: self.position + (other.position - self.position) * alpha,
+            angle: self.angle + (other.angle - self.angle) * alpha,
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use super::vec2::Pos2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MassProperties {
+    pub mass: f32,
+    /// World space center of mass.
+    pub center: Pos2,
+    /// Moment of inertia about `center`.
+    pub inertia: f32,
+}
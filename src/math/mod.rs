@@ -1,3 +1,6 @@
+pub mod aabb;
 pub mod collision_manifold;
+pub mod isometry;
+pub mod mass_properties;
 pub mod ray;
 pub mod vec2;
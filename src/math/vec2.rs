@@ -1,22 +1,20 @@
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
-use macroquad::shapes::draw_circle;
-
-use crate::util::{DrawTransform, Drawable, Transform, FG, RED};
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct Pos2 {
     pub x: f32,
     pub y: f32,
 }
 
 impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0., y: 0. };
+
     pub fn length_squared(self) -> f32 {
         self.x.powi(2) + self.y.powi(2)
     }
@@ -42,6 +40,14 @@ impl Vec2 {
         }
     }
 
+    /// Counter-clockwise perpendicular, so `r.perpendicular() * w` is the 2d `w × r`.
+    pub fn perpendicular(self) -> Self {
+        Self {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+
     pub fn dot(self, other: Self) -> f32 {
         self.x * other.x + self.y * other.y
     }
@@ -213,33 +219,3 @@ impl Neg for Vec2 {
         }
     }
 }
-
-impl Drawable for Pos2 {
-    fn draw(&self, transform: &crate::util::DrawTransform) {
-        let transformed = transform.transform(*self);
-
-        draw_circle(transformed.x, transformed.y, 5., FG);
-    }
-}
-
-impl Transform<Vec2> for DrawTransform {
-    type Output = Vec2;
-
-    fn transform(&self, item: Vec2) -> Vec2 {
-        Vec2 {
-            x: item.x * self.zoom,
-            y: item.y * self.zoom,
-        }
-    }
-}
-
-impl Transform<Pos2> for DrawTransform {
-    type Output = Pos2;
-
-    fn transform(&self, item: Pos2) -> Self::Output {
-        Pos2 {
-            x: item.x * self.zoom,
-            y: item.y * self.zoom,
-        }
-    }
-}
@@ -1,8 +1,11 @@
-use macroquad::shapes::{draw_circle_lines, draw_line};
+use std::f32::consts::PI;
 
-use crate::{
-    math::{collision_manifold::CollisionManifold, ray::Ray, vec2::Pos2},
-    util::{Drawable, Transform, DEVLINE_THICKNESS, FG, GREEN},
+use crate::math::{
+    aabb::Aabb,
+    collision_manifold::{CollisionManifold, ContactId, ContactPoint},
+    mass_properties::MassProperties,
+    ray::{Ray, RayHit},
+    vec2::{Pos2, Vec2},
 };
 
 use super::{Collision, Shape};
@@ -11,34 +14,87 @@ use super::{Collision, Shape};
 pub struct Circle {
     pub position: Pos2,
     pub radius: f32,
+    pub theta: f32,
+}
+
+impl Circle {
+    pub fn new(position: Pos2, radius: f32) -> Circle {
+        Circle {
+            position,
+            radius,
+            theta: 0.,
+        }
+    }
 }
 
 impl Shape for Circle {
-    fn displace(&mut self, delta: crate::math::vec2::Vec2) {
+    fn displace(&mut self, delta: Vec2) {
         self.position += delta;
     }
 
-    fn rotate(&mut self, _theta: f32) {}
+    fn rotate(&mut self, theta: f32) {
+        self.theta += theta;
+    }
 
     fn center(&self) -> Pos2 {
         self.position
     }
-}
 
-impl Drawable for Circle {
-    fn draw(&self, transform: &crate::util::DrawTransform) {
-        let t_center = transform.transform(self.position);
-        let t_radius = transform.transform(self.radius);
-
-        draw_circle_lines(t_center.x, t_center.y, t_radius, DEVLINE_THICKNESS, FG);
-        draw_line(
-            t_center.x,
-            t_center.y,
-            t_center.x + t_radius,
-            t_center.y,
-            DEVLINE_THICKNESS,
-            FG,
-        );
+    fn angle(&self) -> f32 {
+        self.theta
+    }
+
+    fn aabb(&self) -> Aabb {
+        let extents = Vec2::with(self.radius, self.radius);
+
+        Aabb::new(self.position - extents, self.position + extents)
+    }
+
+    fn mass_properties(&self, density: f32) -> MassProperties {
+        let mass = density * PI * self.radius * self.radius;
+
+        MassProperties {
+            mass,
+            center: self.position,
+            inertia: 0.5 * mass * self.radius * self.radius,
+        }
+    }
+
+    fn support(&self, direction: Vec2) -> Pos2 {
+        if direction.length_squared() == 0. {
+            return self.position;
+        }
+
+        self.position + direction.normalized() * self.radius
+    }
+
+    fn contains_point(&self, point: Pos2) -> bool {
+        (point - self.position).length_squared() <= self.radius * self.radius
+    }
+
+    fn raycast(&self, ray: &Ray, max_t: f32) -> Option<RayHit> {
+        // Solves |origin + direction * t - position| = radius for the smaller t.
+        let offset = ray.origin - self.position;
+        let a = ray.direction.length_squared();
+        let b = offset.dot(ray.direction);
+        let c = offset.length_squared() - self.radius * self.radius;
+
+        let discriminant = b * b - a * c;
+        if c < 0. || a == 0. || discriminant < 0. {
+            return None;
+        }
+
+        let fraction = (-b - discriminant.sqrt()) / a;
+        if !(0. ..=max_t).contains(&fraction) {
+            return None;
+        }
+
+        let point = ray.at(fraction);
+        Some(RayHit {
+            fraction,
+            point,
+            normal: (point - self.position).normalized(),
+        })
     }
 }
 
@@ -50,16 +106,21 @@ impl Collision for Circle {
 
         if dist2 < sum_radius.powi(2) {
             let dist = dist2.sqrt();
-            let normal = dir / dist;
+            // Circles sharing a center can be pushed apart any way, so pick one.
+            let normal = if dist > 0. {
+                dir / dist
+            } else {
+                Vec2::with(1., 0.)
+            };
             let depth = sum_radius - dist;
-            Some(CollisionManifold {
-                depth,
+            Some(CollisionManifold::new(
                 normal,
-                penetration: Ray {
-                    origin: self.position + normal * self.radius,
-                    direction: normal * depth,
-                },
-            })
+                &[ContactPoint::new(
+                    self.position + normal * self.radius,
+                    depth,
+                    ContactId::default(),
+                )],
+            ))
         } else {
             None
         }
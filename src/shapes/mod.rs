@@ -1,14 +1,16 @@
 use circle::Circle;
-use polygon::Polygon;
+use polygon::{Polygon, SeparatingAxis};
 
 use crate::{
+    collision,
     math::{
-        collision_manifold::CollisionManifold,
-        ray::Ray,
+        aabb::Aabb,
+        collision_manifold::{CollisionManifold, ContactId, ContactPoint},
+        isometry::Isometry,
+        mass_properties::MassProperties,
+        ray::{Ray, RayHit},
         vec2::{Pos2, Vec2},
     },
-    simulation::Simulation,
-    util::Drawable,
 };
 
 pub mod circle;
@@ -21,12 +23,41 @@ pub trait Shape {
     fn rotate(&mut self, theta: f32);
 
     fn center(&self) -> Pos2;
+
+    fn angle(&self) -> f32;
+
+    fn isometry(&self) -> Isometry {
+        Isometry::new(self.center(), self.angle())
+    }
+
+    /// Moves and rotates the shape so that its center and angle match `isometry`.
+    fn set_isometry(&mut self, isometry: Isometry) {
+        self.displace(isometry.position - self.center());
+        self.rotate(isometry.angle - self.angle());
+    }
+
+    /// World space bounds of the shape.
+    fn aabb(&self) -> Aabb;
+
+    /// Mass, center of mass and moment of inertia of the shape filled with uniform `density`.
+    fn mass_properties(&self, density: f32) -> MassProperties;
+
+    /// The point of the shape furthest along `direction`, in world space. `direction` doesn't have to be
+    /// normalized.
+    fn support(&self, direction: Vec2) -> Pos2;
+
+    /// True if `point` lies inside the shape or on its edge.
+    fn contains_point(&self, point: Pos2) -> bool;
+
+    /// Where `ray` first enters the shape before `max_t`. Rays that start inside the shape don't hit it.
+    fn raycast(&self, ray: &Ray, max_t: f32) -> Option<RayHit>;
 }
 
 pub trait Collision<T = Self> {
     fn collides(&self, other: &T) -> Option<CollisionManifold>;
 }
 
+#[derive(Clone, Debug)]
 pub enum ShapeVariant {
     Circle(Circle),
     Polygon(Polygon),
@@ -53,13 +84,46 @@ impl Shape for ShapeVariant {
             ShapeVariant::Polygon(polygon) => polygon.center(),
         }
     }
-}
 
-impl Drawable for ShapeVariant {
-    fn draw(&self, transform: &crate::util::DrawTransform) {
+    fn angle(&self) -> f32 {
+        match self {
+            ShapeVariant::Circle(circle) => circle.angle(),
+            ShapeVariant::Polygon(polygon) => polygon.angle(),
+        }
+    }
+
+    fn aabb(&self) -> Aabb {
+        match self {
+            ShapeVariant::Circle(circle) => circle.aabb(),
+            ShapeVariant::Polygon(polygon) => polygon.aabb(),
+        }
+    }
+
+    fn mass_properties(&self, density: f32) -> MassProperties {
+        match self {
+            ShapeVariant::Circle(circle) => circle.mass_properties(density),
+            ShapeVariant::Polygon(polygon) => polygon.mass_properties(density),
+        }
+    }
+
+    fn support(&self, direction: Vec2) -> Pos2 {
+        match self {
+            ShapeVariant::Circle(circle) => circle.support(direction),
+            ShapeVariant::Polygon(polygon) => polygon.support(direction),
+        }
+    }
+
+    fn contains_point(&self, point: Pos2) -> bool {
         match self {
-            ShapeVariant::Circle(circle) => circle.draw(transform),
-            ShapeVariant::Polygon(polygon) => polygon.draw(transform),
+            ShapeVariant::Circle(circle) => circle.contains_point(point),
+            ShapeVariant::Polygon(polygon) => polygon.contains_point(point),
+        }
+    }
+
+    fn raycast(&self, ray: &Ray, max_t: f32) -> Option<RayHit> {
+        match self {
+            ShapeVariant::Circle(circle) => circle.raycast(ray, max_t),
+            ShapeVariant::Polygon(polygon) => polygon.raycast(ray, max_t),
         }
     }
 }
@@ -71,13 +135,31 @@ impl Collision for ShapeVariant {
             (ShapeVariant::Polygon(p1), ShapeVariant::Polygon(p2)) => p1.collides(p2),
             (ShapeVariant::Circle(c1), ShapeVariant::Polygon(p2)) => c1.collides(p2),
             (ShapeVariant::Polygon(p1), ShapeVariant::Circle(c2)) => p1.collides(c2),
+            // Pairs without a specialised test go through GJK and EPA.
+            #[allow(unreachable_patterns)]
+            (a, b) => collision::collide(a, b),
+        }
+    }
+}
+
+impl ShapeVariant {
+    /// `collides` that reuses a separating axis from the last test of the same pair, see
+    /// `Polygon::collides_cached`. Only polygon pairs use the cache.
+    pub fn collides_cached(
+        &self,
+        other: &Self,
+        cache: &mut Option<SeparatingAxis>,
+    ) -> Option<CollisionManifold> {
+        match (self, other) {
+            (ShapeVariant::Polygon(p1), ShapeVariant::Polygon(p2)) => p1.collides_cached(p2, cache),
+            _ => self.collides(other),
         }
     }
 }
 
 impl Collision<Circle> for Polygon {
     fn collides(&self, other: &Circle) -> Option<CollisionManifold> {
-        circle_vs_edges(other, &self).or_else(|| circle_vs_points(other, self.get_world_points()))
+        circle_vs_polygon(other, self)
     }
 }
 
@@ -89,66 +171,78 @@ impl Collision<Polygon> for Circle {
     }
 }
 
-fn circle_vs_edges(circle: &Circle, poly: &Polygon) -> Option<CollisionManifold> {
-    let real_points = poly.get_world_points_cycled().collect::<Vec<Pos2>>();
-    let normals = poly.get_world_normals().zip(real_points.windows(2));
-
-    let mut nearest = None;
-
-    for (normal, p) in normals {
-        let vert_to_circ = circle.position - p[0];
-        let vert_to_next = p[1] - p[0];
-        let vert_to_next_len = vert_to_next.length();
-        let vert_to_next = vert_to_next.normalized();
-
-        let circ_next_proj = vert_to_circ.dot(vert_to_next);
-        let circ_norm_proj = vert_to_circ.dot(normal);
-        if circ_next_proj > 0. && circ_next_proj < vert_to_next_len && circ_norm_proj >= 0. {
-            nearest = Some(Ray {
-                origin: p[0],
-                direction: normal,
-            })
+/// Collides a circle with a convex polygon, with the normal pointing from the polygon to the circle.
+///
+/// Finds the face the center is furthest in front of, then whether the center lies in front of that face or
+/// past one of its ends, where the closest feature is a vertex instead. A center inside the polygon is pushed
+/// out through that face.
+fn circle_vs_polygon(circle: &Circle, poly: &Polygon) -> Option<CollisionManifold> {
+    let points: Vec<Pos2> = poly.get_world_points().collect();
+
+    let mut separation = f32::MIN;
+    let mut face = 0;
+    for (index, (normal, &point)) in poly.get_world_normals().zip(&points).enumerate() {
+        let s = normal.dot(circle.position - point);
+
+        if s > circle.radius {
+            return None;
+        }
+        if s > separation {
+            separation = s;
+            face = index;
         }
     }
 
-    let nearest = nearest?;
-
-    let vert_to_circ = circle.position - nearest.origin;
-    let proj_to_norm = nearest.direction.dot(vert_to_circ);
-    if proj_to_norm - circle.radius < 0. {
-        let depth = circle.radius - proj_to_norm;
-        Some(CollisionManifold {
-            normal: nearest.direction,
-            penetration: Ray {
-                origin: circle.position + nearest.direction * circle.radius * -1.,
-                direction: nearest.direction * depth,
-            },
-            depth,
-        })
+    let next = (face + 1) % points.len();
+    let (v1, v2) = (points[face], points[next]);
+    let face_normal = poly.normals[face].rotate(poly.theta);
+
+    let (normal, depth, id) = if separation < f32::EPSILON {
+        // The center is inside, the face it is closest to is the way out.
+        (
+            face_normal,
+            circle.radius - separation,
+            ContactId::new(face, 0),
+        )
+    } else if (circle.position - v1).dot(v2 - v1) <= 0. {
+        circle_vs_vertex(circle, v1, face)?
+    } else if (circle.position - v2).dot(v1 - v2) <= 0. {
+        circle_vs_vertex(circle, v2, next)?
     } else {
-        None
-    }
+        (
+            face_normal,
+            circle.radius - separation,
+            ContactId::new(face, 0),
+        )
+    };
+
+    Some(CollisionManifold::new(
+        normal,
+        &[ContactPoint::new(
+            circle.position - normal * circle.radius,
+            depth,
+            id,
+        )],
+    ))
 }
-fn circle_vs_points(
-    circle: &Circle,
-    poly: impl Iterator<Item = Pos2>,
-) -> Option<CollisionManifold> {
-    for v in poly {
-        let dir = v - circle.position;
-
-        if dir.length_squared() < circle.radius * circle.radius {
-            let pen_depth = circle.radius - dir.length();
-            let normal = -dir.normalized();
-            return Some(CollisionManifold {
-                normal,
-                penetration: Ray {
-                    origin: v,
-                    direction: normal * pen_depth,
-                },
-                depth: pen_depth,
-            });
-        }
+
+/// Normal, depth and id of a circle whose closest feature on the polygon is `vertex`.
+fn circle_vs_vertex(circle: &Circle, vertex: Pos2, index: usize) -> Option<(Vec2, f32, ContactId)> {
+    let offset = circle.position - vertex;
+    let distance_squared = offset.length_squared();
+
+    if distance_squared > circle.radius * circle.radius {
+        return None;
     }
 
-    None
+    let distance = distance_squared.sqrt();
+    Some((
+        offset / distance,
+        circle.radius - distance,
+        ContactId {
+            reference_edge: ContactId::NO_FEATURE,
+            incident_vertex: index as u8,
+            flipped: false,
+        },
+    ))
 }
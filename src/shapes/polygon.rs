@@ -1,17 +1,14 @@
 use std::iter::once;
 
-use macroquad::shapes::draw_line;
-
-use crate::{
-    math::{
-        collision_manifold::CollisionManifold,
-        ray::Ray,
-        vec2::{Pos2, Vec2},
-    },
-    util::{Drawable, Transform, DEVLINE_THICKNESS, FG, GREEN},
+use crate::math::{
+    aabb::Aabb,
+    collision_manifold::{CollisionManifold, ContactId, ContactPoint},
+    mass_properties::MassProperties,
+    ray::{Ray, RayHit},
+    vec2::{Pos2, Vec2},
 };
 
-use super::{circle::Circle, Collision, Shape};
+use super::{Collision, Shape};
 
 #[derive(Clone, Debug)]
 pub struct Polygon {
@@ -91,38 +88,78 @@ impl Shape for Polygon {
     fn center(&self) -> Pos2 {
         self.position
     }
-}
 
-impl Drawable for Polygon {
-    fn draw(&self, transform: &crate::util::DrawTransform) {
-        let points: Vec<Pos2> = self
-            .get_world_points_cycled()
-            .map(|x| transform.transform(x))
-            .collect();
+    fn angle(&self) -> f32 {
+        self.theta
+    }
 
-        for p in points.windows(2) {
-            draw_line(p[0].x, p[0].y, p[1].x, p[1].y, DEVLINE_THICKNESS, FG)
-        }
+    fn aabb(&self) -> Aabb {
+        Aabb::from_points(self.get_world_points())
+            .unwrap_or(Aabb::new(self.position, self.position))
+    }
 
-        // Normals
-        let real_points = self.get_world_points_cycled().collect::<Vec<Pos2>>();
-        let normals = self.get_world_normals().zip(real_points.windows(2));
+    fn mass_properties(&self, density: f32) -> MassProperties {
+        let local_center = centroid(&self.points);
 
-        for (normal, p) in normals {
-            let origin = p[0].midpoint(p[1]);
-            (Ray {
-                origin,
-                direction: normal,
+        MassProperties {
+            mass: density * area(&self.points).abs(),
+            center: self.position + local_center.from_origin().rotate(self.theta),
+            inertia: density * inertia(&self.points, local_center).abs(),
+        }
+    }
+
+    fn support(&self, direction: Vec2) -> Pos2 {
+        self.get_world_points()
+            .max_by(|a, b| {
+                a.from_origin()
+                    .dot(direction)
+                    .total_cmp(&b.from_origin().dot(direction))
             })
-            .draw_line(transform);
+            .unwrap_or(self.position)
+    }
+
+    fn contains_point(&self, point: Pos2) -> bool {
+        self.get_world_points()
+            .zip(self.get_world_normals())
+            .all(|(vertex, normal)| normal.dot(point - vertex) <= 0.)
+    }
+
+    fn raycast(&self, ray: &Ray, max_t: f32) -> Option<RayHit> {
+        // Clips the ray against the inside of every face, it hits the face that clipped its start last.
+        let mut lower = 0.;
+        let mut upper = max_t;
+        let mut face = None;
+
+        for (point, normal) in self.get_world_points().zip(self.get_world_normals()) {
+            let numerator = normal.dot(point - ray.origin);
+            let denominator = normal.dot(ray.direction);
+
+            if denominator == 0. {
+                // Parallel to the face, and entirely outside it.
+                if numerator < 0. {
+                    return None;
+                }
+            } else if denominator < 0. && numerator < lower * denominator {
+                lower = numerator / denominator;
+                face = Some(normal);
+            } else if denominator > 0. && numerator < upper * denominator {
+                upper = numerator / denominator;
+            }
+
+            if upper < lower {
+                return None;
+            }
         }
 
-        // Origin
-        self.position.draw(transform);
+        face.map(|normal| RayHit {
+            fraction: lower,
+            point: ray.at(lower),
+            normal,
+        })
     }
 }
 
-pub fn area(points: &Vec<Vec2>) -> f32 {
+pub fn area(points: &[Vec2]) -> f32 {
     let mut area = 0.;
 
     for i in 0..points.len() {
@@ -133,7 +170,7 @@ pub fn area(points: &Vec<Vec2>) -> f32 {
     area * 0.5
 }
 
-pub fn centroid(points: &Vec<Vec2>) -> Pos2 {
+pub fn centroid(points: &[Vec2]) -> Pos2 {
     let area = area(points);
     let cent_mult = 1. / (6. * area);
 
@@ -151,7 +188,23 @@ pub fn centroid(points: &Vec<Vec2>) -> Pos2 {
     centroid
 }
 
-fn normals(points: &Vec<Vec2>) -> Vec<Vec2> {
+/// Second moment of area about `center`, multiply by density to get the moment of inertia.
+pub fn inertia(points: &[Vec2], center: Pos2) -> f32 {
+    let mut inertia = 0.;
+
+    for i in 0..points.len() {
+        let next = (i + 1).rem_euclid(points.len());
+        let current = points[i] - center.from_origin();
+        let next = points[next] - center.from_origin();
+
+        inertia +=
+            current.cross(next) * (current.dot(current) + current.dot(next) + next.dot(next));
+    }
+
+    inertia / 12.
+}
+
+fn normals(points: &[Vec2]) -> Vec<Vec2> {
     let mut normals = Vec::with_capacity(points.len());
 
     for i in 0..points.len() {
@@ -167,65 +220,193 @@ fn normals(points: &Vec<Vec2>) -> Vec<Vec2> {
 
 impl Collision for Polygon {
     fn collides(&self, other: &Self) -> Option<CollisionManifold> {
-        let contact_a = get_contact_point(self, other)?;
-        let contact_b = get_contact_point(other, self)?;
+        self.collides_cached(other, &mut None)
+    }
+}
 
-        if contact_a.depth < contact_b.depth {
-            Some(contact_a)
+/// Face of one of two polygons that kept them apart, remembered so the next test can try it first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeparatingAxis {
+    pub face: usize,
+    /// Set when the face belongs to the other polygon.
+    pub flipped: bool,
+}
+
+impl Polygon {
+    /// Separating axis test over the faces of both polygons, clipping a manifold if none of them separates.
+    ///
+    /// `cache` is the axis that separated the pair last time. Polygons that stay apart usually stay apart along
+    /// the same axis, so it is checked first and updated with whatever separates them now.
+    pub fn collides_cached(
+        &self,
+        other: &Polygon,
+        cache: &mut Option<SeparatingAxis>,
+    ) -> Option<CollisionManifold> {
+        let points_a: Vec<Pos2> = self.get_world_points().collect();
+        let points_b: Vec<Pos2> = other.get_world_points().collect();
+
+        if let Some(axis) = *cache {
+            let separation = if axis.flipped {
+                face_separation(other, &points_b, axis.face, &points_a)
+            } else {
+                face_separation(self, &points_a, axis.face, &points_b)
+            };
+
+            if separation > 0. {
+                return None;
+            }
+        }
+
+        let (face_a, separation_a) = max_separation(self, &points_a, &points_b);
+        if separation_a > 0. {
+            *cache = Some(SeparatingAxis {
+                face: face_a,
+                flipped: false,
+            });
+            return None;
+        }
+
+        let (face_b, separation_b) = max_separation(other, &points_b, &points_a);
+        if separation_b > 0. {
+            *cache = Some(SeparatingAxis {
+                face: face_b,
+                flipped: true,
+            });
+            return None;
+        }
+
+        *cache = None;
+
+        // Bias towards `self` as the reference so near-equal depths don't flip the manifold every frame.
+        if separation_a + REFERENCE_FACE_TOLERANCE >= separation_b {
+            clip_contacts(self, face_a, other)
         } else {
-            Some(-contact_b)
+            clip_contacts(other, face_b, self).map(|manifold| -manifold)
         }
     }
 }
 
-struct SupportPoint {
-    pub vertex: Pos2,
-    pub penetration: f32,
+const REFERENCE_FACE_TOLERANCE: f32 = 0.01;
+
+/// How far `b` is in front of `a`'s face, negative when it reaches behind it.
+fn face_separation(a: &Polygon, a_points: &[Pos2], face: usize, b_points: &[Pos2]) -> f32 {
+    let Some(&point) = a_points.get(face) else {
+        return f32::MIN;
+    };
+    let normal = a.normals[face].rotate(a.theta);
+
+    b_points
+        .iter()
+        .map(|&v| normal.dot(v - point))
+        .fold(f32::MAX, f32::min)
 }
 
-fn get_contact_point(a: &Polygon, b: &Polygon) -> Option<CollisionManifold> {
-    let mut contact = None;
-    let mut minimum_penetration = f32::MAX;
-
-    let a_world_points = a.get_world_points();
-    let a_world_normals = a.get_world_normals();
-    let b_world_points: Vec<Pos2> = b.get_world_points().collect();
-
-    for (p, n) in a_world_points.zip(a_world_normals) {
-        let support_point = find_support_point(n, p, &b_world_points)?;
-
-        if support_point.penetration < minimum_penetration {
-            minimum_penetration = support_point.penetration;
-            contact = Some(CollisionManifold {
-                normal: n,
-                penetration: Ray {
-                    origin: support_point.vertex,
-                    direction: n * minimum_penetration,
-                },
-                depth: minimum_penetration,
-            });
+/// The face of `a` that `b` is furthest in front of. The polygons overlap if no face has a positive separation.
+fn max_separation(a: &Polygon, a_points: &[Pos2], b_points: &[Pos2]) -> (usize, f32) {
+    (0..a_points.len())
+        .map(|face| (face, face_separation(a, a_points, face, b_points)))
+        .fold(
+            (0, f32::MIN),
+            |best, face| {
+                if face.1 > best.1 {
+                    face
+                } else {
+                    best
+                }
+            },
+        )
+}
+
+/// Clips the face of `incident` that opposes `reference`'s face against that face's side planes, keeping the
+/// points that end up behind it.
+fn clip_contacts(
+    reference: &Polygon,
+    reference_index: usize,
+    incident: &Polygon,
+) -> Option<CollisionManifold> {
+    let reference_points: Vec<Pos2> = reference.get_world_points().collect();
+    let normal = reference.normals[reference_index].rotate(reference.theta);
+    let v1 = reference_points[reference_index];
+    let v2 = reference_points[(reference_index + 1).rem_euclid(reference_points.len())];
+
+    let incident_points: Vec<Pos2> = incident.get_world_points().collect();
+    let incident_index = incident
+        .get_world_normals()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.dot(normal).total_cmp(&b.dot(normal)))
+        .map(|(i, _)| i)?;
+    let incident_next = (incident_index + 1).rem_euclid(incident_points.len());
+    let incident_face = [
+        ClipVertex {
+            point: incident_points[incident_index],
+            vertex: incident_index,
+        },
+        ClipVertex {
+            point: incident_points[incident_next],
+            vertex: incident_next,
+        },
+    ];
+
+    let tangent = (v2 - v1).normalized();
+    let clipped = clip_segment(incident_face, -tangent, -tangent.dot(v1.from_origin()))?;
+    let clipped = clip_segment(clipped, tangent, tangent.dot(v2.from_origin()))?;
+
+    let mut contacts = Vec::with_capacity(2);
+    for clip_vertex in clipped {
+        let depth = -(clip_vertex.point - v1).dot(normal);
+
+        if depth >= 0. {
+            contacts.push(ContactPoint::new(
+                clip_vertex.point,
+                depth,
+                ContactId::new(reference_index, clip_vertex.vertex),
+            ));
         }
     }
 
-    contact
+    if contacts.is_empty() {
+        None
+    } else {
+        Some(CollisionManifold::new(normal, &contacts))
+    }
 }
 
-fn find_support_point(n: Vec2, p: Pos2, b_world_points: &[Pos2]) -> Option<SupportPoint> {
-    let mut deepest_penetration: f32 = 0.;
-    let mut support = None;
-
-    for &v in b_world_points {
-        let vertice_to_point = v - p;
-        let penetration = -(vertice_to_point.dot(n));
+#[derive(Clone, Copy)]
+struct ClipVertex {
+    pub point: Pos2,
+    // Incident vertex this point came from. Points created by clipping keep the vertex they replaced.
+    pub vertex: usize,
+}
 
-        if penetration > deepest_penetration {
-            deepest_penetration = penetration;
-            support = Some(SupportPoint {
-                vertex: v,
-                penetration,
-            });
+/// Keeps the part of `segment` where `normal.dot(point) <= offset`.
+fn clip_segment(segment: [ClipVertex; 2], normal: Vec2, offset: f32) -> Option<[ClipVertex; 2]> {
+    let [v0, v1] = segment;
+    let d0 = normal.dot(v0.point.from_origin()) - offset;
+    let d1 = normal.dot(v1.point.from_origin()) - offset;
+
+    match (d0 <= 0., d1 <= 0.) {
+        (true, true) => Some(segment),
+        (false, false) => None,
+        _ => {
+            let intersection = v0.point + (v1.point - v0.point) * (d0 / (d0 - d1));
+
+            if d0 <= 0. {
+                Some([
+                    v0,
+                    ClipVertex {
+                        point: intersection,
+                        vertex: v1.vertex,
+                    },
+                ])
+            } else {
+                Some([
+                    ClipVertex {
+                        point: intersection,
+                        vertex: v0.vertex,
+                    },
+                    v1,
+                ])
+            }
         }
     }
-
-    support
 }
@@ -1,80 +1,867 @@
-use macroquad::{input::is_key_down, time::get_frame_time};
+use std::collections::BTreeMap;
 
 use crate::{
-    math::vec2::Vec2,
-    shapes::{Collision, Shape, ShapeVariant},
-    util::{DrawTransform, Drawable},
+    broadphase::{dynamic_tree::DynamicTree, Broadphase, ProxyId},
+    collision::distance::distance,
+    collision::shape_cast::{shape_cast, ShapeCastHit},
+    collision::time_of_impact::{time_of_impact, Sweep, TimeOfImpact},
+    dynamics::{
+        contact_cache::{BodyPair, ContactCache},
+        island::find_islands,
+        rigid_body::{BodyType, RigidBody},
+        solver::{ContactSolver, Softness, SolverKind},
+    },
+    math::{
+        aabb::Aabb,
+        collision_manifold::{CollisionManifold, ContactId, ContactPoint},
+        isometry::Isometry,
+        ray::{Ray, RayHit},
+        vec2::{Pos2, Vec2},
+    },
+    shapes::{circle::Circle, polygon::SeparatingAxis, Collision, Shape, ShapeVariant},
 };
 
+// Gap below which speculative contacts are made even for bodies at rest.
+const SPECULATIVE_DISTANCE: f32 = 0.02;
+// Most times a bullet is moved back to an impact in one step.
+const MAX_BULLET_SUB_STEPS: usize = 4;
+
+/// Requests from outside the engine, applied in the order they were sent at the start of the next step.
+#[derive(Debug, Clone, Copy)]
+pub enum Command {
+    /// Pushes on the center of mass of `items[body]` for one step.
+    ApplyForce {
+        body: usize,
+        force: Vec2,
+    },
+    /// Spins `items[body]` for one step.
+    ApplyTorque {
+        body: usize,
+        torque: f32,
+    },
+    /// Sets the control force and torque of `items[body]`, which keep acting on every step until set again.
+    SetControl {
+        body: usize,
+        force: Vec2,
+        torque: f32,
+    },
+    ToggleSolver,
+}
+
 pub struct Simulation {
-    pub items: Vec<ShapeVariant>,
+    pub items: Vec<RigidBody>,
+    /// Acceleration applied to every body, scaled by `RigidBody::gravity_scale`. Positive y points down.
+    pub gravity: Vec2,
+    pub contacts: ContactCache,
+    /// Finds the pairs the narrowphase runs on, a `DynamicTree` unless changed with `set_broadphase`.
+    pub broadphase: Box<dyn Broadphase>,
+    // Broadphase proxy of every body, by index in `items`.
+    proxies: Vec<ProxyId>,
+    pairs: Vec<(usize, usize)>,
+    // Axis that kept each separated polygon pair apart on the last step.
+    separating_axes: BTreeMap<BodyPair, SeparatingAxis>,
+    pub solver_enabled: bool,
+    /// `SolverKind::Iterations` by default.
+    pub solver: SolverKind,
+    /// Also makes contacts for pairs that aren't touching yet but could close the gap within a step, so the
+    /// solver stops them as they meet instead of after they overlap. A cheaper guard against tunneling than
+    /// bullets, though these contacts don't bounce. They show up in `contacts` with a negative depth.
+    pub speculative_contacts: bool,
+    /// Lets islands that have come to rest fall asleep, see `RigidBody::is_sleeping`.
+    pub sleeping_enabled: bool,
+    /// Speed every body of an island has to stay under for `time_to_sleep` before the island sleeps.
+    pub linear_sleep_tolerance: f32,
+    /// Angular speed in radians per second, like `linear_sleep_tolerance`.
+    pub angular_sleep_tolerance: f32,
+    pub time_to_sleep: f32,
+    // Dynamic bodies grouped by what they touch, rebuilt every step.
+    islands: Vec<Vec<usize>>,
+    pub velocity_iterations: usize,
+    pub position_iterations: usize,
+    /// Length of a single physics step used by `step`.
+    pub fixed_dt: f32,
+    /// Most steps `step` will run for one call, the rest of the time is dropped so a slow frame can't snowball.
+    pub max_steps: usize,
+    accumulator: f32,
+    commands: Vec<Command>,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Simulation {
     pub fn new() -> Self {
-        Self { items: vec![] }
+        Self {
+            items: vec![],
+            gravity: Vec2::ZERO,
+            contacts: ContactCache::new(),
+            broadphase: Box::new(DynamicTree::new()),
+            proxies: vec![],
+            pairs: vec![],
+            separating_axes: BTreeMap::new(),
+            solver_enabled: true,
+            solver: SolverKind::Iterations,
+            speculative_contacts: false,
+            sleeping_enabled: true,
+            linear_sleep_tolerance: 0.01,
+            angular_sleep_tolerance: 2f32.to_radians(),
+            time_to_sleep: 0.5,
+            islands: vec![],
+            velocity_iterations: 8,
+            position_iterations: 3,
+            fixed_dt: 1. / 60.,
+            max_steps: 8,
+            accumulator: 0.,
+            commands: vec![],
+        }
+    }
+
+    /// Swaps in a different broadphase strategy. Every body gets a proxy in it on the next step.
+    pub fn set_broadphase(&mut self, broadphase: impl Broadphase + 'static) {
+        self.broadphase = Box::new(broadphase);
+        self.proxies.clear();
+    }
+
+    /// Brings the broadphase up to date with `items`, which `update` does on its own. Queries only find bodies
+    /// where the broadphase last saw them, so call this after moving bodies by hand between steps. Bodies pushed
+    /// onto `items` since are found either way.
+    pub fn sync_broadphase(&mut self) {
+        self.update_broadphase(0.);
+        self.broadphase.commit();
+    }
+
+    /// Queues `command` for the start of the next step.
+    pub fn send(&mut self, command: Command) {
+        self.commands.push(command);
     }
 
+    /// Advances the simulation by `elapsed` real time in steps of `fixed_dt`, returning how many steps ran.
+    /// Time that doesn't fill a whole step is kept for the next call, see `alpha`.
+    pub fn step(&mut self, elapsed: f32) -> usize {
+        self.accumulator += elapsed;
+
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_dt {
+            if steps == self.max_steps {
+                self.accumulator = 0.;
+                break;
+            }
+
+            self.update(self.fixed_dt);
+            self.accumulator -= self.fixed_dt;
+            steps += 1;
+        }
+
+        steps
+    }
+
+    /// How far the leftover time is into the next step, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.fixed_dt).clamp(0., 1.)
+    }
+
+    /// Runs a single step of length `dt`.
     pub fn update(&mut self, dt: f32) {
-        if is_key_down(macroquad::input::KeyCode::W) {
-            self.items[0].displace(Vec2 {
-                x: 0.,
-                y: -5. * get_frame_time(),
-            });
+        self.apply_commands();
+        self.wake_moved_contacts();
+
+        for body in &mut self.items {
+            body.previous = body.shape.isometry();
         }
-        if is_key_down(macroquad::input::KeyCode::S) {
-            self.items[0].displace(Vec2 {
-                x: 0.,
-                y: 5. * get_frame_time(),
-            });
+
+        // The soft step solver integrates velocities itself, once every substep.
+        if !self.solver_enabled || self.solver == SolverKind::Iterations {
+            for body in &mut self.items {
+                body.integrate_velocity(dt, self.gravity);
+            }
         }
-        if is_key_down(macroquad::input::KeyCode::A) {
-            self.items[0].displace(Vec2 {
-                y: 0.,
-                x: -5. * get_frame_time(),
-            });
+
+        self.update_broadphase(dt);
+        self.find_contacts(dt);
+        self.update_islands();
+
+        match self.solver {
+            _ if !self.solver_enabled => {
+                for body in &mut self.items {
+                    body.integrate_position(dt);
+                }
+            }
+            SolverKind::Iterations => self.solve_iterations(dt),
+            SolverKind::SoftStep {
+                substeps,
+                contact_hertz,
+                damping_ratio,
+            } => self.solve_soft_step(dt, substeps.max(1), contact_hertz, damping_ratio),
         }
-        if is_key_down(macroquad::input::KeyCode::D) {
-            self.items[0].displace(Vec2 {
-                y: 0.,
-                x: 5. * get_frame_time(),
-            });
+
+        for body in &mut self.items {
+            body.clear_forces();
         }
-        if is_key_down(macroquad::input::KeyCode::Q) {
-            self.items[0].rotate(-get_frame_time());
+
+        self.solve_bullets(dt);
+        self.update_sleep(dt);
+
+        // Again now the bodies have moved, so queries between steps see where they are.
+        self.update_broadphase(dt);
+        self.broadphase.commit();
+    }
+
+    fn solve_iterations(&mut self, dt: f32) {
+        let mut solver = ContactSolver::new(&self.items, &self.contacts, dt);
+        solver.warm_start(&mut self.items);
+        for _ in 0..self.velocity_iterations {
+            solver.solve_velocities(&mut self.items);
         }
-        if is_key_down(macroquad::input::KeyCode::E) {
-            self.items[0].rotate(get_frame_time());
+
+        for body in &mut self.items {
+            body.integrate_position(dt);
+        }
+
+        for _ in 0..self.position_iterations {
+            if solver.solve_positions(&mut self.items) {
+                break;
+            }
+        }
+        solver.store_impulses(&mut self.contacts);
+    }
+
+    /// Each substep integrates velocities, solves the contacts softly, moves the bodies and then relaxes the
+    /// contacts. The contacts are only found once, at the start of the step.
+    fn solve_soft_step(
+        &mut self,
+        dt: f32,
+        substeps: usize,
+        contact_hertz: f32,
+        damping_ratio: f32,
+    ) {
+        let h = dt / substeps as f32;
+        // Stiffer than a quarter of the substep rate and the springs stop being stable.
+        let softness = Softness::new(contact_hertz.min(0.25 / h), damping_ratio, h);
+
+        let mut solver = ContactSolver::new(&self.items, &self.contacts, dt);
+        for _ in 0..substeps {
+            for body in &mut self.items {
+                body.integrate_velocity(h, self.gravity);
+            }
+
+            solver.warm_start(&mut self.items);
+            solver.solve_soft(&mut self.items, h, &softness, true);
+
+            for body in &mut self.items {
+                body.integrate_position(h);
+            }
+
+            solver.solve_soft(&mut self.items, h, &softness, false);
         }
 
-        let t = DrawTransform {
-            x: 0.,
-            y: 0.,
-            zoom: 25.,
+        solver.apply_restitution(&mut self.items);
+        solver.store_impulses(&mut self.contacts);
+    }
+
+    /// Any body `ray` hits before `max_t`, not necessarily the closest, for when it only matters whether
+    /// something is in the way. Bodies `filter` returns false for are ignored.
+    pub fn raycast(
+        &self,
+        ray: &Ray,
+        max_t: f32,
+        mut filter: impl FnMut(usize, &RigidBody) -> bool,
+    ) -> Option<(usize, RayHit)> {
+        let mut found = None;
+        self.cast_ray(ray, max_t, &mut filter, &mut |index, hit| {
+            found = Some((index, hit));
+            -1.
+        });
+
+        found
+    }
+
+    /// The first body `ray` hits before `max_t`, skipping bodies `filter` returns false for.
+    pub fn raycast_closest(
+        &self,
+        ray: &Ray,
+        max_t: f32,
+        mut filter: impl FnMut(usize, &RigidBody) -> bool,
+    ) -> Option<(usize, RayHit)> {
+        let mut closest = None;
+        self.cast_ray(ray, max_t, &mut filter, &mut |index, hit| {
+            closest = Some((index, hit));
+            hit.fraction
+        });
+
+        closest
+    }
+
+    /// Every body `ray` hits before `max_t`, nearest first, skipping bodies `filter` returns false for.
+    pub fn raycast_all(
+        &self,
+        ray: &Ray,
+        max_t: f32,
+        mut filter: impl FnMut(usize, &RigidBody) -> bool,
+    ) -> Vec<(usize, RayHit)> {
+        let mut hits = Vec::new();
+        self.cast_ray(ray, max_t, &mut filter, &mut |index, hit| {
+            hits.push((index, hit));
+            max_t
+        });
+
+        hits.sort_by(|(_, a), (_, b)| a.fraction.total_cmp(&b.fraction));
+        hits
+    }
+
+    /// Every body containing `point`, in index order.
+    pub fn query_point(&self, point: Pos2) -> Vec<usize> {
+        let mut bodies = Vec::new();
+        self.query_broadphase(&Aabb::new(point, point), &mut |index| {
+            if self
+                .items
+                .get(index)
+                .is_some_and(|body| body.shape.contains_point(point))
+            {
+                bodies.push(index);
+            }
+            true
+        });
+
+        bodies.sort_unstable();
+        bodies
+    }
+
+    /// Every body whose bounds overlap `aabb`, in index order.
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<usize> {
+        let mut bodies = Vec::new();
+        self.query_broadphase(aabb, &mut |index| {
+            if self
+                .items
+                .get(index)
+                .is_some_and(|body| body.shape.aabb().overlaps(aabb))
+            {
+                bodies.push(index);
+            }
+            true
+        });
+
+        bodies.sort_unstable();
+        bodies
+    }
+
+    /// Every body `shape` overlaps, in index order, with the manifold from `shape` to the body.
+    pub fn query_shape(&self, shape: &ShapeVariant) -> Vec<(usize, CollisionManifold)> {
+        let mut bodies = Vec::new();
+        self.query_broadphase(&shape.aabb(), &mut |index| {
+            if let Some(manifold) = self
+                .items
+                .get(index)
+                .and_then(|body| shape.collides(&body.shape))
+            {
+                bodies.push((index, manifold));
+            }
+            true
+        });
+
+        bodies.sort_unstable_by_key(|&(index, _)| index);
+        bodies
+    }
+
+    /// Sweeps `shape`, placed at `transform`, along `translation` and finds the first body it touches, skipping
+    /// bodies `filter` returns false for. Bodies it already overlaps are hit at a fraction of 0.
+    pub fn shape_cast(
+        &self,
+        shape: &ShapeVariant,
+        transform: Isometry,
+        translation: Vec2,
+        mut filter: impl FnMut(usize, &RigidBody) -> bool,
+    ) -> Option<(usize, ShapeCastHit)> {
+        let mut shape = shape.clone();
+        shape.set_isometry(transform);
+
+        let mut closest: Option<(usize, ShapeCastHit)> = None;
+        self.query_broadphase(&shape.aabb().swept(translation), &mut |index| {
+            let Some(body) = self.items.get(index) else {
+                return true;
+            };
+            if !filter(index, body) {
+                return true;
+            }
+
+            if let Some(hit) = shape_cast(&shape, translation, &body.shape) {
+                if closest.is_none_or(|(_, closest)| hit.fraction < closest.fraction) {
+                    closest = Some((index, hit));
+                }
+            }
+            true
+        });
+
+        closest
+    }
+
+    /// Walks the broadphase along `ray`, calling `on_hit` for the bodies it hits. `on_hit` returns the `max_t`
+    /// to carry on with, or a negative value to stop.
+    fn cast_ray(
+        &self,
+        ray: &Ray,
+        max_t: f32,
+        filter: &mut dyn FnMut(usize, &RigidBody) -> bool,
+        on_hit: &mut dyn FnMut(usize, RayHit) -> f32,
+    ) {
+        let mut current_t = max_t;
+        let mut max_t = max_t;
+        let mut visit = |index: usize| {
+            // Proxies of bodies removed since the last step are still around.
+            let Some(body) = self.items.get(index) else {
+                return max_t;
+            };
+            if !filter(index, body) {
+                return max_t;
+            }
+
+            if let Some(hit) = body.shape.raycast(ray, max_t) {
+                max_t = on_hit(index, hit);
+            }
+            max_t
         };
 
-        if is_key_down(macroquad::input::KeyCode::Space) {
-            for i in 0..self.items.len() {
-                for j in 0..self.items.len() {
-                    if i == j {
-                        continue;
+        self.broadphase.raycast(ray, current_t, &mut |index| {
+            current_t = visit(index);
+            current_t
+        });
+
+        // Bodies added since the last step don't have a proxy yet.
+        for index in self.proxies.len()..self.items.len() {
+            if current_t < 0. {
+                break;
+            }
+            current_t = visit(index);
+        }
+    }
+
+    /// Calls `callback` with every body whose proxy overlaps `aabb`, then with every body added since the last
+    /// step, which doesn't have a proxy yet. Returning false from the callback stops the query.
+    fn query_broadphase(&self, aabb: &Aabb, callback: &mut dyn FnMut(usize) -> bool) {
+        let mut carry_on = true;
+        self.broadphase.query(aabb, &mut |index| {
+            carry_on = callback(index);
+            carry_on
+        });
+
+        for index in self.proxies.len()..self.items.len() {
+            if !carry_on {
+                break;
+            }
+            carry_on = callback(index);
+        }
+    }
+
+    fn apply_commands(&mut self) {
+        for command in std::mem::take(&mut self.commands) {
+            match command {
+                Command::ApplyForce { body, force } => {
+                    if let Some(body) = self.items.get_mut(body) {
+                        body.apply_force(force);
+                    }
+                }
+                Command::ApplyTorque { body, torque } => {
+                    if let Some(body) = self.items.get_mut(body) {
+                        body.apply_torque(torque);
+                    }
+                }
+                Command::SetControl {
+                    body,
+                    force,
+                    torque,
+                } => {
+                    if let Some(body) = self.items.get_mut(body) {
+                        body.set_control(force, torque);
+                    }
+                }
+                Command::ToggleSolver => self.solver_enabled = !self.solver_enabled,
+            }
+        }
+    }
+
+    /// Sweeps every bullet from where it started the step to where it ended up. One that passed into another
+    /// body is moved back to the first impact, bounced off it, and moved on for the rest of the step, a few
+    /// times over at most. After the last time it stays at the impact and the rest of the step is dropped, rather
+    /// than moving it on unchecked. The bodies it is swept against are taken to be where they ended the step.
+    fn solve_bullets(&mut self, dt: f32) {
+        for i in 0..self.items.len() {
+            let body = &self.items[i];
+            if !body.bullet || !body.is_dynamic() || body.is_sleeping() {
+                continue;
+            }
+
+            let mut sweep = body.sweep(body.previous);
+            let mut remaining = dt;
+
+            for sub_step in 0..MAX_BULLET_SUB_STEPS {
+                let Some((j, impact)) = self.first_impact(i, &sweep) else {
+                    break;
+                };
+
+                self.items[i].set_pose(sweep.isometry(impact.fraction));
+
+                // The impact normal goes from the bullet to the other body, manifolds from the lower index.
+                let normal = if i < j { impact.normal } else { -impact.normal };
+                let manifold = CollisionManifold::new(
+                    normal,
+                    &[ContactPoint::new(
+                        impact.point_a.midpoint(impact.point_b),
+                        0.,
+                        ContactId::default(),
+                    )],
+                );
+
+                self.items[j].wake_up();
+                let mut impacts = ContactCache::new();
+                impacts.update([((i.min(j), i.max(j)), manifold)]);
+                let mut solver = ContactSolver::new(&self.items, &impacts, dt);
+                for _ in 0..self.velocity_iterations {
+                    solver.solve_velocities(&mut self.items);
+                }
+
+                if sub_step + 1 == MAX_BULLET_SUB_STEPS {
+                    break;
+                }
+
+                let body = &mut self.items[i];
+                let start = body.shape.isometry();
+                remaining *= 1. - impact.fraction;
+                body.integrate_position(remaining);
+                sweep = body.sweep(start);
+            }
+        }
+    }
+
+    /// The first body the bullet `items[index]` hits along `sweep`.
+    ///
+    /// Bodies the bullet already touches are hit at the start, which would stop it dead, so they're only hit if
+    /// a small circle at its center of mass would hit them too. That lets it slide along them and spin against
+    /// them, but never turn or push its way through.
+    fn first_impact(&self, index: usize, sweep: &Sweep) -> Option<(usize, TimeOfImpact)> {
+        let bullet = &self.items[index];
+        let mut start = bullet.shape.clone();
+        start.set_isometry(sweep.isometry(0.));
+        let mut end = bullet.shape.clone();
+        end.set_isometry(sweep.isometry(1.));
+        let core = Circle::new(
+            sweep.center_start,
+            0.25 * min_extent(&start, sweep.center_start),
+        );
+
+        let mut first: Option<(usize, TimeOfImpact)> = None;
+        self.broadphase
+            .query(&start.aabb().union(&end.aabb()), &mut |other| {
+                let Some(body) = self.items.get(other).filter(|_| other != index) else {
+                    return true;
+                };
+
+                let other_sweep = Sweep::stationary(body.shape.isometry());
+                let impact = time_of_impact(&start, sweep, &body.shape, &other_sweep)
+                    .filter(|impact| impact.fraction > 0.)
+                    .or_else(|| {
+                        let core_sweep = Sweep {
+                            shape_offset: Vec2::ZERO,
+                            ..*sweep
+                        };
+                        time_of_impact(&core, &core_sweep, &body.shape, &other_sweep)
+                            .filter(|impact| impact.fraction > 0.)
+                    });
+
+                if let Some(impact) = impact {
+                    if first.is_none_or(|(_, first)| impact.fraction < first.fraction) {
+                        first = Some((other, impact));
                     }
+                }
+                true
+            });
 
-                    if let Some(col) = self.items[i].collides(&self.items[j]) {
-                        let half_dir = col.penetration.direction * 0.5;
-                        self.items[i].displace(-half_dir);
-                        self.items[j].displace(half_dir);
+        first
+    }
+
+    /// Keeps a broadphase proxy for every body, moving those whose shape left its fat AABB. Bodies pushed onto
+    /// `items` since the last step get a proxy here.
+    fn update_broadphase(&mut self, dt: f32) {
+        while self.proxies.len() > self.items.len() {
+            let proxy = self.proxies.pop().unwrap();
+            self.broadphase.destroy_proxy(proxy);
+        }
+
+        for (i, body) in self.items.iter().enumerate() {
+            let mut aabb = body.shape.aabb();
+            // Speculative contacts need the pair before the bodies meet, so cover where the body is heading.
+            if self.speculative_contacts {
+                aabb = aabb.swept(body.velocity * dt);
+            }
+
+            match self.proxies.get(i) {
+                Some(&proxy) => {
+                    self.broadphase.move_proxy(proxy, aabb, body.velocity * dt);
+                }
+                None => self.proxies.push(self.broadphase.create_proxy(aabb, i)),
+            }
+        }
+    }
+
+    /// Rebuilds the manifolds of every overlapping pair the broadphase reports, except between bodies that are
+    /// asleep or can't move, which keep theirs. The cache matches them against the previous step.
+    fn find_contacts(&mut self, dt: f32) {
+        self.pairs.clear();
+        self.broadphase.find_pairs(&mut self.pairs);
+        self.pairs.sort_unstable();
+
+        let mut manifolds = Vec::new();
+        let mut previous_axes = std::mem::take(&mut self.separating_axes);
+        let mut woken = Vec::new();
+
+        for &(i, j) in &self.pairs {
+            let (a, b) = (&self.items[i], &self.items[j]);
+
+            // Without a dynamic body nothing would respond to the contact.
+            if !a.is_dynamic() && !b.is_dynamic() {
+                continue;
+            }
+
+            let mut axis = previous_axes.remove(&(i, j));
+            let manifold = if !is_moving(a) && !is_moving(b) {
+                // Neither body moved since the last step, so whatever touched then still does.
+                self.contacts.get((i, j)).map(|contact| contact.manifold)
+            } else {
+                let manifold = match a.shape.collides_cached(&b.shape, &mut axis) {
+                    None if self.speculative_contacts => speculative_manifold(a, b, dt),
+                    manifold => manifold,
+                };
+                // Something moving reached a sleeping body.
+                if manifold.is_some() {
+                    woken.extend([i, j].into_iter().filter(|&k| self.items[k].is_sleeping()));
+                }
+                manifold
+            };
+
+            if let Some(manifold) = manifold {
+                manifolds.push(((i, j), manifold));
+            }
+            if let Some(axis) = axis {
+                self.separating_axes.insert((i, j), axis);
+            }
+        }
+
+        for i in woken {
+            self.items[i].wake_up();
+        }
+        self.contacts.update(manifolds);
+    }
+
+    /// Groups the bodies by what they touch, and wakes every island that has a body awake in it.
+    /// Wakes the bodies touching a static or kinematic body whose pose changed since the last step, like ground
+    /// moved by hand. Their cached contacts are stale, and asleep they would be left floating or buried.
+    fn wake_moved_contacts(&mut self) {
+        let moved: Vec<bool> = self
+            .items
+            .iter()
+            .map(|body| {
+                let (now, then) = (body.shape.isometry(), body.previous);
+                !body.is_dynamic()
+                    && ((now.position - then.position).length_squared() > 0.
+                        || now.angle != then.angle)
+            })
+            .collect();
+
+        // The contacts are from the last step, bodies removed since are skipped.
+        for ((i, j), _) in self.contacts.iter() {
+            for (support, other) in [(i, j), (j, i)] {
+                if !moved.get(support).is_some_and(|&moved| moved) {
+                    continue;
+                }
+                if let Some(body) = self.items.get_mut(other).filter(|body| body.is_sleeping()) {
+                    body.wake_up();
+                }
+            }
+        }
+    }
+
+    fn update_islands(&mut self) {
+        self.islands = find_islands(&self.items, &self.contacts);
+
+        for island in &self.islands {
+            if island.iter().any(|&i| !self.items[i].is_sleeping()) {
+                for &i in island {
+                    if self.items[i].is_sleeping() {
+                        self.items[i].wake_up();
                     }
                 }
             }
         }
     }
+
+    /// Times how long each body has been resting and puts islands where every body has rested for
+    /// `time_to_sleep` to sleep. An island can only sleep as a whole, or the bodies still awake would fall
+    /// into the sleeping ones.
+    fn update_sleep(&mut self, dt: f32) {
+        if !self.sleeping_enabled {
+            for body in &mut self.items {
+                body.wake_up();
+            }
+            return;
+        }
+
+        let linear = self.linear_sleep_tolerance * self.linear_sleep_tolerance;
+        let angular = self.angular_sleep_tolerance * self.angular_sleep_tolerance;
+        for body in &mut self.items {
+            if body.velocity.length_squared() > linear
+                || body.angular_velocity * body.angular_velocity > angular
+                || body.is_controlled()
+            {
+                body.sleep_time = 0.;
+            } else {
+                body.sleep_time += dt;
+            }
+        }
+
+        for island in &self.islands {
+            if island
+                .iter()
+                .all(|&i| self.items[i].sleep_time >= self.time_to_sleep)
+            {
+                for &i in island {
+                    self.items[i].sleep();
+                }
+            }
+        }
+    }
+}
+
+/// A contact between two bodies that are apart, if they move fast enough to meet within `dt`. The margin
+/// grows with how fast they could close in, spin included.
+fn speculative_manifold(a: &RigidBody, b: &RigidBody, dt: f32) -> Option<CollisionManifold> {
+    let reach =
+        |body: &RigidBody| body.angular_velocity.abs() * body.shape.aabb().extents().length();
+    let speed = (b.velocity - a.velocity).length() + reach(a) + reach(b);
+    let margin = SPECULATIVE_DISTANCE + speed * dt;
+
+    let distance = distance(&a.shape, &b.shape);
+    if distance.distance <= 0. || distance.distance > margin {
+        return None;
+    }
+
+    Some(CollisionManifold::new(
+        distance.normal,
+        &[ContactPoint::new(
+            distance.point_a.midpoint(distance.point_b),
+            -distance.distance,
+            ContactId::default(),
+        )],
+    ))
+}
+
+/// Distance from `center` to the closest point on the edge of `shape`, or 0 if `center` isn't inside it.
+fn min_extent(shape: &ShapeVariant, center: Pos2) -> f32 {
+    let extent = match shape {
+        ShapeVariant::Circle(circle) => circle.radius - (center - circle.position).length(),
+        ShapeVariant::Polygon(polygon) => polygon
+            .get_world_points()
+            .zip(polygon.get_world_normals())
+            .map(|(point, normal)| normal.dot(point - center))
+            .fold(f32::MAX, f32::min),
+    };
+
+    extent.max(0.)
+}
+
+/// Whether the body could have moved since the last step. Kinematic bodies only count while they have a
+/// velocity, so one left standing still doesn't keep everything on it awake.
+fn is_moving(body: &RigidBody) -> bool {
+    match body.body_type() {
+        BodyType::Static => false,
+        BodyType::Kinematic => body.velocity.length_squared() > 0. || body.angular_velocity != 0.,
+        BodyType::Dynamic => !body.is_sleeping(),
+    }
 }
 
-impl Drawable for Simulation {
-    fn draw(&self, transform: &crate::util::DrawTransform) {
-        for i in &self.items {
-            i.draw(transform)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::polygon::Polygon;
+
+    /// Fires `bullet` at a thin wall `x` ahead of it, tilted by `angle`, and returns how far past the wall
+    /// it ends up, negative while it stays in front.
+    fn fire_at_wall(bullet: RigidBody, x: f32, angle: f32) -> f32 {
+        let mut sim = Simulation::new();
+        let mut wall = Polygon::rectangle(bullet.shape.center() + Vec2::with(x, 0.), 0.05, 6.);
+        wall.rotate(angle);
+        let wall_center = wall.position;
+        sim.items
+            .push(RigidBody::new_static(ShapeVariant::Polygon(wall)));
+        sim.items.push(bullet);
+
+        for _ in 0..10 {
+            sim.update(1. / 60.);
+        }
+
+        (sim.items[1].shape.center() - wall_center).dot(Vec2::with(angle.cos(), angle.sin()))
+    }
+
+    fn bullet(shape: ShapeVariant, velocity: Vec2, angular_velocity: f32) -> RigidBody {
+        let mut body = RigidBody::from_shape(shape, 1.);
+        body.velocity = velocity;
+        body.angular_velocity = angular_velocity;
+        body.bullet = true;
+        body
+    }
+
+    #[test]
+    fn circle_bullet_stops_at_tilted_thin_wall() {
+        for angle in [0., 0.01, 0.3] {
+            for i in 0..20 {
+                let x = 1. + 0.37 * i as f32;
+                let circle = Circle::new(Pos2::at(0., 0.5), 0.05);
+                let bullet = bullet(ShapeVariant::Circle(circle), Vec2::with(500., 0.), 0.);
+
+                let past = fire_at_wall(bullet, x, angle);
+                assert!(past < 0., "went {past} past a wall {x} away at {angle}");
+            }
+        }
+    }
+
+    #[test]
+    fn spinning_rod_bullet_stops_at_thin_wall() {
+        for angle in [0., 0.01, 0.3] {
+            for i in 0..20 {
+                let x = 1. + 0.37 * i as f32;
+                let rod = Polygon::rectangle(Pos2::at(0., 0.5), 0.8, 0.05);
+                let bullet = bullet(ShapeVariant::Polygon(rod), Vec2::with(300., 0.), 60.);
+
+                let past = fire_at_wall(bullet, x, angle);
+                assert!(past < 0., "went {past} past a wall {x} away at {angle}");
+            }
+        }
+    }
+
+    #[test]
+    fn sleeping_stack_wakes_when_ground_moves() {
+        let mut sim = Simulation::new();
+        sim.gravity = Vec2::with(0., 9.81);
+        let rectangle =
+            |y, width| ShapeVariant::Polygon(Polygon::rectangle(Pos2::at(0., y), width, 1.));
+        sim.items.push(RigidBody::new_static(rectangle(20., 40.)));
+        for row in 0..3 {
+            sim.items
+                .push(RigidBody::from_shape(rectangle(18.5 - row as f32, 1.), 1.));
+        }
+
+        for _ in 0..300 {
+            sim.update(1. / 60.);
+        }
+        assert!(sim.items[1..].iter().all(RigidBody::is_sleeping));
+
+        sim.items[0].shape.displace(Vec2::with(0., 100.));
+        sim.update(1. / 60.);
+        assert!(sim.items[1..].iter().all(|body| !body.is_sleeping()));
+
+        for _ in 0..30 {
+            sim.update(1. / 60.);
         }
+        assert!(sim.items[3].shape.center().y > 17.);
     }
 }
@@ -0,0 +1,186 @@
+use macroquad::shapes::{draw_circle, draw_circle_lines, draw_line};
+
+use crate::{
+    dynamics::rigid_body::RigidBody,
+    math::{
+        ray::Ray,
+        vec2::{Pos2, Vec2},
+    },
+    shapes::{circle::Circle, polygon::Polygon, ShapeVariant},
+    simulation::Simulation,
+};
+
+use super::{DrawTransform, Drawable, Transform, DEVLINE_THICKNESS, FG};
+
+const ARROWHEAD_LENGTH: f32 = 30.;
+const ARROWHEAD_HALFWIDTH: f32 = 12.;
+
+impl Ray {
+    pub fn draw_line(&self, transform: &DrawTransform) {
+        let origin_transform = transform.transform(self.origin);
+        let direction_transform = transform.transform(self.direction);
+
+        let arrow_end = origin_transform + direction_transform;
+
+        draw_line(
+            origin_transform.x,
+            origin_transform.y,
+            arrow_end.x,
+            arrow_end.y,
+            DEVLINE_THICKNESS,
+            FG,
+        );
+    }
+}
+
+impl Drawable for Pos2 {
+    fn draw(&self, transform: &DrawTransform) {
+        let transformed = transform.transform(*self);
+
+        draw_circle(transformed.x, transformed.y, 5., FG);
+    }
+}
+
+impl Transform<Vec2> for DrawTransform {
+    type Output = Vec2;
+
+    fn transform(&self, item: Vec2) -> Vec2 {
+        Vec2 {
+            x: item.x * self.zoom,
+            y: item.y * self.zoom,
+        }
+    }
+}
+
+impl Transform<Pos2> for DrawTransform {
+    type Output = Pos2;
+
+    fn transform(&self, item: Pos2) -> Self::Output {
+        Pos2 {
+            x: item.x * self.zoom,
+            y: item.y * self.zoom,
+        }
+    }
+}
+
+impl Drawable for Ray {
+    fn draw(&self, transform: &DrawTransform) {
+        let origin_transform = transform.transform(self.origin);
+        let direction_transform = transform.transform(self.direction);
+
+        let normal = direction_transform.normalized();
+        let ortho = normal.orthogonal();
+        let arrow_end = origin_transform + direction_transform;
+        let arrow_tip_0 = arrow_end - normal * ARROWHEAD_LENGTH - ortho * ARROWHEAD_HALFWIDTH;
+        let arrow_tip_1 = arrow_end - normal * ARROWHEAD_LENGTH + ortho * ARROWHEAD_HALFWIDTH;
+
+        draw_line(
+            origin_transform.x,
+            origin_transform.y,
+            arrow_end.x,
+            arrow_end.y,
+            DEVLINE_THICKNESS,
+            FG,
+        );
+
+        draw_line(
+            arrow_end.x,
+            arrow_end.y,
+            arrow_tip_0.x,
+            arrow_tip_0.y,
+            DEVLINE_THICKNESS,
+            FG,
+        );
+        draw_line(
+            arrow_end.x,
+            arrow_end.y,
+            arrow_tip_1.x,
+            arrow_tip_1.y,
+            DEVLINE_THICKNESS,
+            FG,
+        );
+    }
+}
+
+impl Transform<Ray> for DrawTransform {
+    type Output = Ray;
+
+    fn transform(&self, item: Ray) -> Ray {
+        Ray {
+            origin: self.transform(item.origin),
+            direction: self.transform(item.direction),
+        }
+    }
+}
+
+impl Drawable for Circle {
+    fn draw(&self, transform: &DrawTransform) {
+        let t_center = transform.transform(self.position);
+        let t_radius = transform.transform(self.radius);
+        let t_spoke = Vec2::with(t_radius, 0.).rotate(self.theta);
+
+        draw_circle_lines(t_center.x, t_center.y, t_radius, DEVLINE_THICKNESS, FG);
+        draw_line(
+            t_center.x,
+            t_center.y,
+            t_center.x + t_spoke.x,
+            t_center.y + t_spoke.y,
+            DEVLINE_THICKNESS,
+            FG,
+        );
+    }
+}
+
+impl Drawable for Polygon {
+    fn draw(&self, transform: &DrawTransform) {
+        let points: Vec<Pos2> = self
+            .get_world_points_cycled()
+            .map(|x| transform.transform(x))
+            .collect();
+
+        for p in points.windows(2) {
+            draw_line(p[0].x, p[0].y, p[1].x, p[1].y, DEVLINE_THICKNESS, FG)
+        }
+
+        // Normals
+        let real_points = self.get_world_points_cycled().collect::<Vec<Pos2>>();
+        let normals = self.get_world_normals().zip(real_points.windows(2));
+
+        for (normal, p) in normals {
+            let origin = p[0].midpoint(p[1]);
+            (Ray {
+                origin,
+                direction: normal,
+            })
+            .draw_line(transform);
+        }
+
+        // Origin
+        self.position.draw(transform);
+    }
+}
+
+impl Drawable for ShapeVariant {
+    fn draw(&self, transform: &DrawTransform) {
+        match self {
+            ShapeVariant::Circle(circle) => circle.draw(transform),
+            ShapeVariant::Polygon(polygon) => polygon.draw(transform),
+        }
+    }
+}
+
+impl Drawable for RigidBody {
+    fn draw(&self, transform: &DrawTransform) {
+        self.shape.draw(transform)
+    }
+}
+
+impl Drawable for Simulation {
+    fn draw(&self, transform: &DrawTransform) {
+        let alpha = self.alpha();
+
+        for i in &self.items {
+            i.interpolated_shape(alpha).draw(transform)
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use macroquad::color::Color;
 
+mod draw;
+
 // Note that this entire file is for dev purposes. Later, any color management and transforms should be done by another library or user.
 
 pub trait Drawable {
@@ -38,37 +40,12 @@ impl DrawTransform {
     }
 }
 
-pub const RED: Color = Color::new(
-    0.8235294117647058,
-    0.058823529411764705,
-    0.2235294117647059,
-    1.,
-);
-pub const GREEN: Color = Color::new(
-    0.25098039215686274,
-    0.6274509803921569,
-    0.16862745098039217,
-    1.0,
-);
-pub const BLUE: Color = Color::new(0.11764705882352941, 0.4, 0.9607843137254902, 1.0);
-pub const YELLOW: Color = Color::new(
-    0.8745098039215686,
-    0.5568627450980392,
-    0.11372549019607843,
-    1.0,
-);
-pub const BG: Color = Color::new(
-    0.9372549019607843,
-    0.9450980392156862,
-    0.9607843137254902,
-    1.0,
-);
-pub const FG: Color = Color::new(
-    0.2980392156862745,
-    0.30980392156862746,
-    0.4117647058823529,
-    1.0,
-);
+pub const RED: Color = Color::new(0.823_529_4, 0.058_823_53, 0.223_529_41, 1.);
+pub const GREEN: Color = Color::new(0.250_980_4, 0.627_451, 0.168_627_46, 1.0);
+pub const BLUE: Color = Color::new(0.117_647_06, 0.4, 0.960_784_3, 1.0);
+pub const YELLOW: Color = Color::new(0.874_509_8, 0.556_862_8, 0.113_725_49, 1.0);
+pub const BG: Color = Color::new(0.937_254_9, 0.945_098_04, 0.960_784_3, 1.0);
+pub const FG: Color = Color::new(0.298_039_23, 0.309_803_93, 0.411_764_7, 1.0);
 
 pub const DEVLINE_THICKNESS: f32 = 3.;
 